@@ -0,0 +1,62 @@
+#!/usr/bin/env python3
+"""A small battleships engine: places ships at random, then fires at random
+until it hits and next to its hits after that. See "Engine protocol" in the
+README."""
+
+import random
+import sys
+
+
+def send(line):
+    print(line, flush=True)
+
+
+def place(width, height, sizes):
+    taken = set()
+    for size in sizes:
+        while True:
+            horizontal = random.random() < 0.5
+            x = random.randrange(width - size + 1 if horizontal else width)
+            y = random.randrange(height if horizontal else height - size + 1)
+            cells = {(x + i, y) if horizontal else (x, y + i) for i in range(size)}
+            if not cells & taken:
+                taken |= cells
+                send(f"ship {x} {y} {'h' if horizontal else 'v'}")
+                break
+
+
+def main():
+    width = height = 0
+    fired = set()
+    hits = set()
+    for line in sys.stdin:
+        command, *args = line.split()
+        if command == "protocol":
+            send("name example.py")
+            send("ok")
+        elif command == "newgame":
+            width, height = int(args[0]), int(args[1])
+            fired.clear()
+            hits.clear()
+        elif command == "place":
+            place(width, height, [int(size) for size in args])
+        elif command == "fire":
+            for _ in range(int(args[0])):
+                unknown = [(x, y) for x in range(width) for y in range(height)
+                           if (x, y) not in fired]
+                near = [(x, y) for (x, y) in unknown
+                        if {(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)} & hits]
+                x, y = random.choice(near or unknown)
+                fired.add((x, y))
+                send(f"shot {x} {y}")
+        elif command == "result":
+            x, y, outcome = int(args[0]), int(args[1]), args[2]
+            fired.add((x, y))
+            if outcome == "hit":
+                hits.add((x, y))
+        elif command == "quit":
+            break
+
+
+if __name__ == "__main__":
+    main()
@@ -1,231 +1,133 @@
-use crate::{Board::Board, Difficulty::Difficulty, Ship::ShipType};
+use crate::{
+    Board::{Board, Shot},
+    Strategies::RandomStrategy,
+    Targeting::{StrategyRegistry, TargetView, TargetingStrategy},
+};
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
+/// How many rejected targets a strategy gets before the AI fires at random.
+const MAX_TARGET_ATTEMPTS: u32 = 100;
+
+#[derive(Serialize, Deserialize)]
 pub struct AI {
-    hit_spots: Vec<u32>,
-    attack_spots: Vec<u32>,
+    strategy_name: String,
+    placement_name: String,
+    #[serde(skip)]
+    strategy: Option<Box<dyn TargetingStrategy>>,
 }
 
 impl AI {
-    pub fn new() -> Self {
+    pub fn new(strategy_name: &str, placement_name: &str) -> Self {
         Self {
-            attack_spots: Vec::new(),
-            hit_spots: Vec::new(),
+            strategy_name: strategy_name.to_string(),
+            placement_name: placement_name.to_string(),
+            strategy: None,
         }
     }
 
-    pub fn place_ships(&self, ai_board: &mut Board) {
-        let mut current_ship_type = <ShipType as num::FromPrimitive>::from_u32(1).unwrap();
-        let mut rng = rand::thread_rng();
-        for i in 0..5 {
-            let current_ship_type_int = num::ToPrimitive::to_u32(&current_ship_type).unwrap();
-
-            let ship_size: u32 = current_ship_type.get_size() as u32;
-
-            loop {
-                let hor_pos = rng.gen_range(0..=(ai_board.get_board_size() - ship_size));
-                let ver_pos = rng.gen_range(0..ai_board.get_board_size());
-
-                let horizontal = rng.gen_bool(0.5);
-
-                let x = if horizontal { hor_pos } else { ver_pos };
-                let y = if horizontal { ver_pos } else { hor_pos };
-
-                let previous_ship_types = ai_board.get_ship_types(x, y, ship_size, horizontal);
-                ai_board.set_ship_type(x, y, current_ship_type, ship_size, horizontal);
-
-                if !ai_board.any_overlap() {
-                    break;
-                }
-
-                ai_board.set_ship_types(x, y, &previous_ship_types, horizontal);
-            }
-
-            current_ship_type =
-                <ShipType as num::FromPrimitive>::from_u32(current_ship_type_int + 1).unwrap();
-        }
+    pub fn get_strategy_name(&self) -> &str {
+        &self.strategy_name
     }
 
-    pub fn do_turn(&mut self, player_board: &mut Board, game_difficulty: Difficulty) {
-        let mut x: u32 = 0;
-        let mut y: u32 = 0;
-
-        let mut rng = rand::thread_rng();
-
-        match game_difficulty {
-            Difficulty::Easy => {
-                let pos = self.random_turn(player_board);
-                x = pos[0];
-                y = pos[1];
-            }
-            Difficulty::Medium => {
-                let option = rng.gen_range(0..=1);
-                match option {
-                    0 => {
-                        let pos = self.random_turn(player_board);
-                        x = pos[0];
-                        y = pos[1];
-                    }
-                    1 | _ => {
-                        let pos = self.smart_turn(player_board);
-                        x = pos[0];
-                        y = pos[1];
-                    }
-                }
-            }
-            Difficulty::Hard => {
-                let option = rng.gen_range(0..=3);
-                match option {
-                    0 => {
-                        let pos = self.corner_turn(player_board);
-                        x = pos[0];
-                        y = pos[1];
-                    }
-                    1 => {
-                        let pos = self.center_turn(player_board);
-                        x = pos[0];
-                        y = pos[1];
-                    }
-                    2 => {
-                        let pos = self.smart_turn(player_board);
-                        x = pos[0];
-                        y = pos[1];
-                    }
-                    3 | _ => {
-                        let pos = self.smart_turn(player_board);
-                        x = pos[0];
-                        y = pos[1];
-                    }
-                }
-            }
-        }
-
-        if x >= player_board.get_board_size() || y >= player_board.get_board_size() {
-            self.do_turn(player_board, game_difficulty);
-            return;
-        }
-
-        if self.has_attack(x, y) {
-            self.do_turn(player_board, game_difficulty);
-            return;
-        }
-
-        if player_board.get_ship(x, y).unwrap().damaged {
-            self.do_turn(player_board, game_difficulty);
-            return;
-        }
-
-        if player_board.hit_ship(x, y) {
-            self.hit_spots.push(x);
-            self.hit_spots.push(y);
-        }
-
-        self.attack_spots.push(x);
-        self.attack_spots.push(y);
+    pub fn get_placement_name(&self) -> &str {
+        &self.placement_name
     }
 
-    fn random_turn(&mut self, player_board: &Board) -> Vec<u32> {
-        let mut rng = rand::thread_rng();
-        vec![
-            rng.gen_range(0..player_board.get_board_size()),
-            rng.gen_range(0..player_board.get_board_size()),
-        ]
+    /// Places the AI's fleet with its placement strategy.
+    pub fn place_ships(
+        &self,
+        ai_board: &mut Board,
+        registry: &StrategyRegistry,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), &'static str> {
+        registry
+            .create_placement(&self.placement_name)
+            .ok_or("The AI's placement strategy is not registered")?
+            .place_fleet(ai_board, rng)
     }
 
-    fn corner_turn(&mut self, player_board: &Board) -> Vec<u32> {
-        let mut rng = rand::thread_rng();
-        let x = rng.gen_range(0..=1) * (player_board.get_board_size() - 1);
-        let y = rng.gen_range(0..=1) * (player_board.get_board_size() - 1);
-
-        vec![x, y]
-    }
-
-    fn center_turn(&mut self, player_board: &Board) -> Vec<u32> {
-        let middle = player_board.get_board_size() / 2;
-        let width = player_board.get_board_size() / 4;
-
-        let mut rng = rand::thread_rng();
-
-        let x = rng.gen_range(width..=(width + middle));
-        let y = rng.gen_range(width..=(width + middle));
-
-        vec![x, y]
-    }
-
-    fn smart_turn(&mut self, player_board: &Board) -> Vec<u32> {
-        if self.hit_spots.len() <= 0 {
-            return self.random_turn(player_board);
+    fn strategy(
+        &mut self,
+        registry: &StrategyRegistry,
+    ) -> Result<&mut Box<dyn TargetingStrategy>, &'static str> {
+        if self.strategy.is_none() {
+            self.strategy = registry.create(&self.strategy_name);
         }
+        self.strategy
+            .as_mut()
+            .ok_or("The AI's strategy is not registered")
+    }
 
-        let mut rng = rand::thread_rng();
-
-        let index = rng.gen_range(0..(self.hit_spots.len() / 2)) * 2;
-
-        let hit_x = self.hit_spots[index];
-        let hit_y = self.hit_spots[index + 1];
-
-        let option = rng.gen_range(0..8);
-
-        let mut x: u32 = 0;
-        let mut y: u32 = 0;
-
-        match option {
-            0 => {
-                x = hit_x - 1;
-                y = hit_y - 1;
-            }
-            1 => {
-                x = hit_x;
-                y = hit_y - 1;
-            }
-            2 => {
-                x = hit_x + 1;
-                y = hit_y - 1;
-            }
-            3 => {
-                x = hit_x - 1;
-                y = hit_y;
-            }
-            4 => {
-                x = hit_x + 1;
-                y = hit_y;
-            }
-            5 => {
-                x = hit_x - 1;
-                y = hit_y + 1;
+    /// Fires one shot at the player's board, chosen by the AI's strategy.
+    pub fn do_turn(
+        &mut self,
+        player_board: &mut Board,
+        registry: &StrategyRegistry,
+        rng: &mut dyn RngCore,
+    ) -> Result<Shot, &'static str> {
+        let strategy = self.strategy(registry)?;
+        let view = TargetView::from_board(player_board);
+
+        let mut attempts = 0;
+        let (x, y) = loop {
+            let target = strategy.next_target(&view, rng);
+            if let Some(e) = strategy.error() {
+                return Err(e);
             }
-            6 => {
-                x = hit_x;
-                y = hit_y + 1;
+            attempts += 1;
+            if view.is_unknown(target.0, target.1) {
+                break target;
             }
-            7 | _ => {
-                x = hit_x + 1;
-                y = hit_y + 1;
+            if attempts >= MAX_TARGET_ATTEMPTS {
+                break RandomStrategy.next_target(&view, rng);
             }
-        }
-
-        if self.has_attack(x, y) {
-            return self.smart_turn(player_board);
-        }
+        };
 
-        vec![x, y]
+        let result = player_board.fire_at(x, y);
+        Ok(Shot { x, y, result })
     }
 
-    fn has_attack(&self, x: u32, y: u32) -> bool {
-        if self.attack_spots.len() <= 0 {
-            return false;
+    /// Picks `count` cells with the AI's strategy, then fires at all of them.
+    /// Fewer shots are fired if fewer cells are left.
+    pub fn do_salvo(
+        &mut self,
+        player_board: &mut Board,
+        count: u32,
+        registry: &StrategyRegistry,
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<Shot>, &'static str> {
+        let strategy = self.strategy(registry)?;
+        let view = TargetView::from_board(player_board);
+        let count = count.min(player_board.unfired_cells());
+
+        // Drop anything the strategy picked twice or already knows, and make
+        // up the numbers at random
+        let salvo = strategy.next_salvo(&view, count, rng);
+        if let Some(e) = strategy.error() {
+            return Err(e);
         }
-
-        let length = self.attack_spots.len() / 2;
-
-        for i in (0..length).step_by(2) {
-            let attack_x = self.attack_spots[i];
-            let attack_y = self.attack_spots[i + 1];
-            if attack_x == x && attack_y == y {
-                return true;
+        let mut chosen = view.clone();
+        let mut targets = Vec::new();
+        for (x, y) in salvo {
+            if targets.len() < count as usize && chosen.is_unknown(x, y) {
+                chosen.mark_targeted(x, y);
+                targets.push((x, y));
             }
         }
+        while targets.len() < count as usize && !chosen.unknown_cells().is_empty() {
+            let (x, y) = RandomStrategy.next_target(&chosen, rng);
+            chosen.mark_targeted(x, y);
+            targets.push((x, y));
+        }
 
-        false
+        Ok(targets
+            .into_iter()
+            .map(|(x, y)| Shot {
+                x,
+                y,
+                result: player_board.fire_at(x, y),
+            })
+            .collect())
     }
 }
@@ -1,111 +1,444 @@
-use crate::Ship::{Ship, ShipType};
+use crate::{
+    Cell::{Cell, Occupant, ShotResult},
+    Fleet::FleetConfig,
+    Rules::{Adjacency, Rules, MAX_BOARD_SIZE},
+    Ship::{Orientation, PlacedShip, ShipType},
+};
 use colored::{Color, Colorize};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
-pub struct Board {
-    board_size: u32,
-    ships: Vec<Ship>,
+const WATER_COLOR: Color = Color::TrueColor { r: 0, g: 0, b: 128 };
+const SHIP_COLOR: Color = Color::TrueColor {
+    r: 128,
+    g: 128,
+    b: 128,
+};
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum FireResult {
+    Miss,
+    Hit,
+    Sunk(ShipType),
+    AlreadyFired,
+    OutOfBounds,
 }
 
-impl Board {
-    pub fn new(board_size: Option<u32>) -> Self {
-        let board_size = board_size.unwrap_or(10);
-        let length = board_size * board_size;
-        let ships: Vec<Ship> = vec![Ship::new(); length as usize];
-        Self { board_size, ships }
+impl FireResult {
+    pub fn is_valid_shot(&self) -> bool {
+        matches!(self, Self::Miss | Self::Hit | Self::Sunk(_))
     }
 
-    pub fn get_ships(&self) -> &Vec<Ship> {
-        &self.ships
+    pub fn is_hit(&self) -> bool {
+        matches!(self, Self::Hit | Self::Sunk(_))
     }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Shot {
+    pub x: u32,
+    pub y: u32,
+    pub result: FireResult,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PlacementError {
+    OutOfBounds,
+    Overlaps(ShipType),
+    TouchesAnotherShip,
+    FleetQuotaExceeded,
+    SetupFinished,
+}
 
-    pub fn get_ship(&mut self, x: u32, y: u32) -> Result<&mut Ship, &'static str> {
-        let index = y * self.board_size + x;
-        match x >= self.board_size || y >= self.board_size {
-            true => Err("Position is not on the board"),
-            false => Ok(self.ships.get_mut(index as usize).unwrap()),
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OutOfBounds => write!(f, "The ship does not fit on the board there"),
+            Self::Overlaps(ship_type) => {
+                write!(f, "The ship overlaps your {}", ship_type.to_string())
+            }
+            Self::TouchesAnotherShip => write!(f, "The ship touches another ship"),
+            Self::FleetQuotaExceeded => write!(f, "There are no ships of this type left to place"),
+            Self::SetupFinished => write!(f, "Ships can only be placed during setup"),
         }
     }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CoordinateError {
+    Empty,
+    Malformed,
+    ColumnOutOfRange(String),
+    RowOutOfRange(String),
+    MissingOrientation,
+    UnknownOrientation(String),
+    TrailingText(String),
+}
 
-    pub fn get_board_size(&self) -> u32 {
-        self.board_size
+impl fmt::Display for CoordinateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Type a position like B7"),
+            Self::Malformed => write!(
+                f,
+                "Positions are a column letter then a row number, like B7"
+            ),
+            Self::ColumnOutOfRange(column) => write!(f, "Column {} is not on the board", column),
+            Self::RowOutOfRange(row) => write!(f, "Row {} is not on the board", row),
+            Self::MissingOrientation => write!(
+                f,
+                "Add h or v after the position to lay the ship horizontally or vertically"
+            ),
+            Self::UnknownOrientation(text) => {
+                write!(f, "'{}' is not a direction, use h or v", text)
+            }
+            Self::TrailingText(text) => write!(f, "Did not expect '{}' after the direction", text),
+        }
     }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Selection {
+    None,
+    Cursor {
+        x: u32,
+        y: u32,
+    },
+    Ship(PlacedShip),
+    /// A cursor along with the cells marked for a salvo.
+    Salvo {
+        x: u32,
+        y: u32,
+        marked: Vec<(u32, u32)>,
+    },
+}
 
-    pub fn any_overlap(&self) -> bool {
-        let ships = self.get_ships();
-        for ship in ships {
-            if ship.damaged {
-                return true;
+impl Selection {
+    fn covers(&self, cell_x: u32, cell_y: u32) -> Option<&str> {
+        match self {
+            Self::None => None,
+            Self::Cursor { x, y } => (*x == cell_x && *y == cell_y).then_some(" + "),
+            Self::Ship(ship) => ship
+                .covers(cell_x, cell_y)
+                .then(|| ship.ship_type.to_shorten()),
+            Self::Salvo { x, y, marked } => {
+                let cursor = *x == cell_x && *y == cell_y;
+                match (cursor, marked.contains(&(cell_x, cell_y))) {
+                    (true, true) => Some("(+)"),
+                    (true, false) => Some(" + "),
+                    (false, true) => Some(" * "),
+                    (false, false) => None,
+                }
             }
         }
-        false
     }
+}
 
-    pub fn all_destroyed(&self) -> bool {
-        let ships = self.get_ships();
-        for ship in ships {
-            match ship.ship_type {
-                ShipType::Empty | ShipType::Selection | ShipType::WaterSelection => continue,
-                _ => {}
+/// Boards read from save files are checked to hold together, so that firing
+/// at them can not index past their cells or a ship's hits.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedBoard")]
+pub struct Board {
+    width: u32,
+    height: u32,
+    cells: Vec<Cell>,
+    ships: Vec<PlacedShip>,
+    fleet: FleetConfig,
+    adjacency: Adjacency,
+    /// Every shot fired at the board, in order, with what it reported. This
+    /// is all the shooter gets to know about the board.
+    #[serde(default)]
+    shots: Vec<Shot>,
+}
+
+#[derive(Deserialize)]
+struct UncheckedBoard {
+    width: u32,
+    height: u32,
+    cells: Vec<Cell>,
+    ships: Vec<PlacedShip>,
+    fleet: FleetConfig,
+    adjacency: Adjacency,
+    #[serde(default)]
+    shots: Vec<Shot>,
+}
+
+impl TryFrom<UncheckedBoard> for Board {
+    type Error = &'static str;
+
+    fn try_from(board: UncheckedBoard) -> Result<Self, Self::Error> {
+        if board.width > MAX_BOARD_SIZE
+            || board.height > MAX_BOARD_SIZE
+            || board.cells.len() != (board.width * board.height) as usize
+        {
+            return Err("The board's cells do not match its size");
+        }
+        for ship in &board.ships {
+            if ship.hits.len() != ship.ship_type.get_size() as usize {
+                return Err("A ship's hits do not match its length");
             }
-            if !ship.damaged {
-                return false;
+        }
+        for (index, cell) in board.cells.iter().enumerate() {
+            let x = index as u32 % board.width;
+            let y = index as u32 / board.width;
+            if let Occupant::Ship(ship_id) = cell.occupant {
+                match board.ships.get(ship_id) {
+                    Some(ship) if ship.covers(x, y) => {}
+                    _ => return Err("A cell holds a ship that is not there"),
+                }
             }
         }
-        true
+        if board
+            .shots
+            .iter()
+            .any(|shot| shot.x >= board.width || shot.y >= board.height)
+        {
+            return Err("A shot is not on the board");
+        }
+
+        Ok(Self {
+            width: board.width,
+            height: board.height,
+            cells: board.cells,
+            ships: board.ships,
+            fleet: board.fleet,
+            adjacency: board.adjacency,
+            shots: board.shots,
+        })
+    }
+}
+
+impl Board {
+    pub fn new(rules: &Rules) -> Self {
+        let length = rules.width * rules.height;
+        let cells: Vec<Cell> = vec![Cell::new(); length as usize];
+        Self {
+            width: rules.width,
+            height: rules.height,
+            cells,
+            ships: Vec::new(),
+            fleet: rules.fleet.clone(),
+            adjacency: rules.adjacency,
+            shots: Vec::new(),
+        }
+    }
+
+    /// Spreadsheet style column names: A to Z, then AA, AB and so on.
+    pub fn column_label(x: u32) -> String {
+        let mut label = String::new();
+        let mut column = x + 1;
+        while column > 0 {
+            column -= 1;
+            label.insert(0, (65 + (column % 26)) as u8 as char);
+            column /= 26;
+        }
+        label
+    }
+
+    pub fn position_label(x: u32, y: u32) -> String {
+        format!("{}{}", Self::column_label(x), y + 1)
     }
 
-    pub fn show(&mut self) {
-        for y in -1..(self.board_size as i32) {
-            for x in -1..(self.board_size as i32) {
+    /// Reads a position written the way `position_label` writes it, such as
+    /// "B7" or "j10".
+    pub fn parse_position(&self, text: &str) -> Result<(u32, u32), CoordinateError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(CoordinateError::Empty);
+        }
+
+        let split = text
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(text.len());
+        let (letters, digits) = text.split_at(split);
+        if letters.is_empty() || digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(CoordinateError::Malformed);
+        }
+
+        let column_error = || CoordinateError::ColumnOutOfRange(letters.to_ascii_uppercase());
+        let mut column: u32 = 0;
+        for letter in letters.bytes() {
+            let value = (letter.to_ascii_uppercase() - b'A') as u32 + 1;
+            column = column
+                .checked_mul(26)
+                .and_then(|column| column.checked_add(value))
+                .ok_or_else(column_error)?;
+        }
+        let x = column - 1;
+        if x >= self.width {
+            return Err(column_error());
+        }
+
+        let y = match digits.parse::<u32>() {
+            Ok(row) if row >= 1 && row <= self.height => row - 1,
+            _ => return Err(CoordinateError::RowOutOfRange(digits.to_string())),
+        };
+        Ok((x, y))
+    }
+
+    /// Reads a position followed by a direction, such as "B7 h" or "c3 v".
+    pub fn parse_placement(&self, text: &str) -> Result<(u32, u32, Orientation), CoordinateError> {
+        let mut parts = text.split_whitespace();
+        let (x, y) = self.parse_position(parts.next().unwrap_or(""))?;
+
+        let orientation = match parts.next().map(|part| part.to_ascii_lowercase()) {
+            None => return Err(CoordinateError::MissingOrientation),
+            Some(part) if part == "h" || part == "horizontal" => Orientation::Horizontal,
+            Some(part) if part == "v" || part == "vertical" => Orientation::Vertical,
+            Some(part) => return Err(CoordinateError::UnknownOrientation(part)),
+        };
+        if let Some(part) = parts.next() {
+            return Err(CoordinateError::TrailingText(part.to_string()));
+        }
+        Ok((x, y, orientation))
+    }
+
+    pub fn get_cells(&self) -> &Vec<Cell> {
+        &self.cells
+    }
+
+    pub fn get_cell(&self, x: u32, y: u32) -> Result<&Cell, &'static str> {
+        if x >= self.width || y >= self.height {
+            return Err("Position is not on the board");
+        }
+        Ok(&self.cells[(y * self.width + x) as usize])
+    }
+
+    fn get_cell_mut(&mut self, x: u32, y: u32) -> Result<&mut Cell, &'static str> {
+        if x >= self.width || y >= self.height {
+            return Err("Position is not on the board");
+        }
+        Ok(&mut self.cells[(y * self.width + x) as usize])
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_fleet(&self) -> &FleetConfig {
+        &self.fleet
+    }
+
+    pub fn get_adjacency(&self) -> Adjacency {
+        self.adjacency
+    }
+
+    /// Cells next to the given one that the adjacency rule keeps clear of other ships.
+    pub fn get_neighbours(&self, x: u32, y: u32) -> Vec<(u32, u32)> {
+        self.adjacency.neighbours(x, y, self.width, self.height)
+    }
+
+    pub fn remaining_ships(&self, ship_type: &ShipType) -> u32 {
+        let placed = self
+            .ships
+            .iter()
+            .filter(|ship| ship.ship_type == *ship_type)
+            .count() as u32;
+        self.fleet.get_count(ship_type).saturating_sub(placed)
+    }
+
+    /// Every ship still to be placed, longest first.
+    pub fn remaining_fleet(&self) -> Vec<ShipType> {
+        let mut ships: Vec<ShipType> = Vec::new();
+        for entry in self.fleet.get_entries() {
+            for _ in 0..self.remaining_ships(&entry.ship_type) {
+                ships.push(entry.ship_type.clone());
+            }
+        }
+        ships.sort_by_key(|ship_type| std::cmp::Reverse(ship_type.get_size()));
+        ships
+    }
+
+    pub fn all_ships_placed(&self) -> bool {
+        self.ships.len() as u32 >= self.fleet.total_ships()
+    }
+
+    pub fn get_ships(&self) -> &Vec<PlacedShip> {
+        &self.ships
+    }
+
+    pub fn get_ship(&self, ship_id: usize) -> Option<&PlacedShip> {
+        self.ships.get(ship_id)
+    }
+
+    pub fn get_ship_at(&self, x: u32, y: u32) -> Option<&PlacedShip> {
+        match self.get_cell(x, y).ok()?.occupant {
+            Occupant::Ship(ship_id) => self.get_ship(ship_id),
+            Occupant::Water => None,
+        }
+    }
+
+    pub fn get_shots(&self) -> &Vec<Shot> {
+        &self.shots
+    }
+
+    pub fn ships_afloat(&self) -> u32 {
+        self.ships.iter().filter(|ship| !ship.is_sunk()).count() as u32
+    }
+
+    pub fn unfired_cells(&self) -> u32 {
+        self.cells.iter().filter(|cell| !cell.is_fired()).count() as u32
+    }
+
+    pub fn all_destroyed(&self) -> bool {
+        let sunk = self.ships.iter().filter(|ship| ship.is_sunk()).count() as u32;
+        sunk >= self.fleet.total_ships()
+    }
+
+    pub fn show(&self, reveal_ships: bool, selection: &Selection) {
+        let line_width = self.height.to_string().len().max(2);
+        for y in -1..(self.height as i32) {
+            for x in -1..(self.width as i32) {
                 if x == -1 && y == -1 {
-                    print!("  |");
+                    print!("{:width$}|", "", width = line_width);
                 } else if x == -1 && y >= 0 {
-                    let line = y + 1;
-                    print!("{}", line.to_string().yellow());
-                    if line < 10 {
-                        print!(" ");
-                    }
-                    print!("|");
+                    let line = (y + 1).to_string();
+                    print!("{}", line.yellow());
+                    print!("{:width$}|", "", width = line_width - line.len());
                 } else if y == -1 && x >= 0 {
-                    print!(" {}", ((65 + x) as u8 as char).to_string().blue());
-                    print!(" |");
+                    let label = Self::column_label(x as u32);
+                    print!("{}|", format!("{:^3}", label).blue());
                 } else {
-                    let ship = self.get_ship(x as u32, y as u32).unwrap();
-                    let repr = ship.ship_type.to_shorten();
-                    let mut background_color: Color = Color::Black;
-                    let mut front_color: Color = Color::White;
-                    if ship.damaged {
-                        match ship.ship_type {
-                            ShipType::Selection | ShipType::WaterSelection => {
-                                background_color = Color::TrueColor {
-                                    r: 128,
-                                    g: 128,
-                                    b: 128,
-                                }
-                            }
-                            _ => {
-                                background_color = Color::TrueColor {
-                                    r: 128,
-                                    g: 128,
-                                    b: 128,
-                                };
-                                front_color = Color::Red;
-                            }
-                        }
-                    } else {
-                        match ship.ship_type {
-                            ShipType::Empty | ShipType::WaterSelection | ShipType::Shot => {
-                                background_color = Color::TrueColor { r: 0, g: 0, b: 128 }
-                            }
-                            _ => {
-                                background_color = Color::TrueColor {
-                                    r: 128,
-                                    g: 128,
-                                    b: 128,
-                                }
-                            }
+                    let cell = self.get_cell(x as u32, y as u32).unwrap();
+                    let ship_type = match cell.occupant {
+                        Occupant::Ship(ship_id) => self
+                            .get_ship(ship_id)
+                            .filter(|ship| reveal_ships || ship.is_sunk())
+                            .map(|ship| &ship.ship_type),
+                        Occupant::Water => None,
+                    };
+
+                    let mut repr = match (ship_type, cell.shot) {
+                        (Some(ship_type), _) => ship_type.to_shorten(),
+                        (None, Some(_)) => " x ",
+                        (None, None) => "",
+                    };
+                    let mut background_color = match (ship_type, cell.shot) {
+                        (Some(_), _) | (None, Some(ShotResult::Hit)) => SHIP_COLOR,
+                        _ => WATER_COLOR,
+                    };
+                    let mut front_color = match cell.shot {
+                        Some(ShotResult::Hit) => Color::Red,
+                        _ => Color::White,
+                    };
+
+                    if let Some(selected) = selection.covers(x as u32, y as u32) {
+                        repr = selected;
+                        if let Selection::Ship(_) = selection {
+                            background_color = SHIP_COLOR;
+                            front_color = match cell.occupant {
+                                Occupant::Ship(_) => Color::Red,
+                                Occupant::Water => Color::White,
+                            };
+                        } else {
+                            front_color = Color::White;
                         }
                     }
+
                     print!("{}", repr.on_color(background_color).color(front_color));
 
                     if repr.len() < 3 {
@@ -115,118 +448,391 @@ impl Board {
                         }
                     }
 
-                    front_color = Color::White;
-                    if x < (self.board_size as i32) - 1 {
-                        background_color = Color::TrueColor { r: 0, g: 0, b: 128 }
+                    let separator_color = if x < (self.width as i32) - 1 {
+                        WATER_COLOR
                     } else {
-                        background_color = Color::Black;
-                    }
+                        Color::Black
+                    };
 
-                    print!("{}", "|".color(front_color).on_color(background_color));
+                    print!("{}", "|".color(Color::White).on_color(separator_color));
                 }
             }
             println!();
         }
     }
 
-    pub fn hit_ship(&mut self, x: u32, y: u32) -> bool {
-        let ship = match self.get_ship(x, y) {
-            Ok(ship) => ship,
-            Err(e) => {
-                println!("{}", e);
-                return false;
+    pub fn fire_at(&mut self, x: u32, y: u32) -> FireResult {
+        let cell = match self.get_cell_mut(x, y) {
+            Ok(cell) => cell,
+            Err(_) => return FireResult::OutOfBounds,
+        };
+        if cell.is_fired() {
+            return FireResult::AlreadyFired;
+        }
+
+        let result = match cell.occupant {
+            Occupant::Water => {
+                cell.shot = Some(ShotResult::Miss);
+                FireResult::Miss
+            }
+            Occupant::Ship(ship_id) => {
+                cell.shot = Some(ShotResult::Hit);
+                let ship = &mut self.ships[ship_id];
+                ship.hit(x, y);
+                match ship.is_sunk() {
+                    true => FireResult::Sunk(ship.ship_type.clone()),
+                    false => FireResult::Hit,
+                }
             }
         };
+        self.shots.push(Shot {
+            x,
+            y,
+            result: result.clone(),
+        });
+        result
+    }
 
-        if ship.ship_type == ShipType::Empty {
-            return false;
+    /// Marks a shot whose result the other side reported, on a board that
+    /// does not know where their ships are.
+    pub fn record_shot(&mut self, x: u32, y: u32, result: ShotResult) -> Result<(), &'static str> {
+        let cell = self.get_cell_mut(x, y)?;
+        if cell.is_fired() {
+            return Err("That cell has already been fired at");
         }
-        if ship.damaged {
-            return false;
+        cell.shot = Some(result);
+        let result = match result {
+            ShotResult::Miss => FireResult::Miss,
+            ShotResult::Hit => FireResult::Hit,
+        };
+        self.shots.push(Shot { x, y, result });
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.cells = vec![Cell::new(); self.cells.len()];
+        self.ships.clear();
+        self.shots.clear();
+    }
+
+    /// Takes the ship covering (x, y) off the board.
+    pub fn remove_ship_at(&mut self, x: u32, y: u32) -> Option<PlacedShip> {
+        let Occupant::Ship(ship_id) = self.get_cell(x, y).ok()?.occupant else {
+            return None;
+        };
+        let ship = self.ships.remove(ship_id);
+
+        // Ships after the removed one move down a place in the list
+        for cell in self.cells.iter_mut() {
+            if let Occupant::Ship(id) = cell.occupant {
+                if id == ship_id {
+                    cell.occupant = Occupant::Water;
+                } else if id > ship_id {
+                    cell.occupant = Occupant::Ship(id - 1);
+                }
+            }
+        }
+        Some(ship)
+    }
+
+    pub fn can_fit_fleet(&self) -> bool {
+        self.find_fleet_layout().is_some()
+    }
+
+    /// A copy of the board with every remaining ship placed legally, if the
+    /// search can find such a layout.
+    pub fn find_fleet_layout(&self) -> Option<Board> {
+        let mut budget: u32 = 200_000;
+        self.fit_remaining(&self.remaining_fleet(), 0, &mut budget)
+    }
+
+    // Depth first search over ship positions, longest ships first. Ships of
+    // the same length are interchangeable, so each one only looks at cells
+    // after the previous one. Running out of budget counts as not fitting.
+    fn fit_remaining(&self, ships: &[ShipType], start: u32, budget: &mut u32) -> Option<Board> {
+        let Some((ship_type, rest)) = ships.split_first() else {
+            return Some(self.clone());
+        };
+
+        let needed_cells: u32 = ships.iter().map(|ship_type| ship_type.get_size()).sum();
+        if self.available_cells() < needed_cells {
+            return None;
+        }
+
+        for index in start..(self.width * self.height) {
+            let x = index % self.width;
+            let y = index / self.width;
+            for orientation in [Orientation::Horizontal, Orientation::Vertical] {
+                if *budget == 0 {
+                    return None;
+                }
+                *budget -= 1;
+
+                if self.can_place(ship_type, x, y, orientation).is_err() {
+                    continue;
+                }
+                let mut board = self.clone();
+                board.place(ship_type, x, y, orientation).unwrap();
+
+                let next_start = match rest.first() {
+                    Some(next) if next.get_size() == ship_type.get_size() => index + 1,
+                    _ => 0,
+                };
+                if let Some(layout) = board.fit_remaining(rest, next_start, budget) {
+                    return Some(layout);
+                }
+            }
         }
-        ship.damaged = true;
-        true
+        None
     }
 
-    pub fn update_ship_selection(&mut self, previous_ship_type: &mut ShipType, x: u32, y: u32) {
-        let ship = match self.get_ship(x, y) {
-            Ok(ship) => ship,
-            Err(e) => {
-                println!("{}", e);
-                return;
+    // Cells a new ship could still use under the adjacency rule.
+    fn available_cells(&self) -> u32 {
+        let mut available = 0;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.get_ship_at(x, y).is_some() {
+                    continue;
+                }
+                let blocked = self
+                    .get_neighbours(x, y)
+                    .into_iter()
+                    .any(|(x, y)| self.get_ship_at(x, y).is_some());
+                if !blocked {
+                    available += 1;
+                }
             }
+        }
+        available
+    }
+
+    pub fn can_place(
+        &self,
+        ship_type: &ShipType,
+        x: u32,
+        y: u32,
+        orientation: Orientation,
+    ) -> Result<(), PlacementError> {
+        if self.remaining_ships(ship_type) == 0 {
+            return Err(PlacementError::FleetQuotaExceeded);
+        }
+
+        let (end_x, end_y) = match orientation {
+            Orientation::Horizontal => (x.checked_add(ship_type.get_size() - 1), Some(y)),
+            Orientation::Vertical => (Some(x), y.checked_add(ship_type.get_size() - 1)),
         };
-        *previous_ship_type = ship.ship_type.clone();
-        if *previous_ship_type == ShipType::Empty
-            || (*previous_ship_type == ShipType::Shot && !ship.damaged)
-        {
-            ship.ship_type = ShipType::WaterSelection;
-        } else {
-            ship.ship_type = ShipType::Selection;
+        match (end_x, end_y) {
+            (Some(end_x), Some(end_y)) if end_x < self.width && end_y < self.height => {}
+            _ => return Err(PlacementError::OutOfBounds),
+        }
+
+        let ship = PlacedShip::new(ship_type.clone(), x, y, orientation);
+        let cells = ship.get_cells();
+        for (cell_x, cell_y) in &cells {
+            if let Some(other) = self.get_ship_at(*cell_x, *cell_y) {
+                return Err(PlacementError::Overlaps(other.ship_type.clone()));
+            }
+        }
+        for (cell_x, cell_y) in &cells {
+            let touching = self
+                .get_neighbours(*cell_x, *cell_y)
+                .into_iter()
+                .any(|(x, y)| self.get_ship_at(x, y).is_some());
+            if touching {
+                return Err(PlacementError::TouchesAnotherShip);
+            }
         }
+        Ok(())
     }
 
-    pub fn set_ship_type(
+    pub fn place(
         &mut self,
+        ship_type: &ShipType,
         x: u32,
         y: u32,
-        ship_type: ShipType,
-        amount: u32,
-        horizontal: bool,
-    ) {
-        for i in 0..amount {
-            let ship = self
-                .get_ship(
-                    x + match horizontal {
-                        true => i,
-                        false => 0,
-                    },
-                    y + match horizontal {
-                        false => i,
-                        true => 0,
-                    },
-                )
-                .unwrap();
-            if ship.ship_type != ship_type && ship.ship_type != ShipType::Empty {
-                ship.damaged = true;
-            } else {
-                ship.damaged = false;
-            }
-            ship.ship_type = ship_type;
-        }
-    }
-
-    pub fn set_ship_types(&mut self, x: u32, y: u32, ship_types: &Vec<ShipType>, horizontal: bool) {
-        for i in 0..ship_types.len() {
-            let ship = self
-                .get_ship(
-                    x + match horizontal {
-                        true => i as u32,
-                        false => 0,
-                    },
-                    y + match horizontal {
-                        false => i as u32,
-                        true => 0,
-                    },
-                )
-                .unwrap();
-            ship.damaged = false;
-            ship.ship_type = ship_types[i];
+        orientation: Orientation,
+    ) -> Result<usize, PlacementError> {
+        self.can_place(ship_type, x, y, orientation)?;
+
+        let ship_id = self.ships.len();
+        let ship = PlacedShip::new(ship_type.clone(), x, y, orientation);
+        for (cell_x, cell_y) in ship.get_cells() {
+            self.get_cell_mut(cell_x, cell_y).unwrap().occupant = Occupant::Ship(ship_id);
         }
+        self.ships.push(ship);
+        Ok(ship_id)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    pub fn get_ship_types(&mut self, x: u32, y: u32, amount: u32, horizontal: bool) -> Vec<ShipType> {
-        let mut ship_types:Vec<ShipType>=Vec::new();
-        for i in 0..amount{
-            ship_types.push(self.get_ship(x + match horizontal {
-                true => i as u32,
-                false => 0,
-            },
-            y + match horizontal {
-                false => i as u32,
-                true => 0,
-            }).unwrap().ship_type.clone());
+    fn ship(name: &str) -> ShipType {
+        FleetConfig::classic()
+            .ships()
+            .into_iter()
+            .find(|ship_type| ship_type.to_string() == name)
+            .unwrap()
+    }
+
+    fn board(adjacency: Adjacency) -> Board {
+        let mut rules = Rules::new();
+        rules.adjacency = adjacency;
+        Board::new(&rules)
+    }
+
+    #[test]
+    fn column_labels_go_on_past_z() {
+        assert_eq!(Board::column_label(0), "A");
+        assert_eq!(Board::column_label(25), "Z");
+        assert_eq!(Board::column_label(26), "AA");
+        assert_eq!(Board::column_label(39), "AN");
+        assert_eq!(Board::column_label(52), "BA");
+        assert_eq!(Board::position_label(27, 9), "AB10");
+    }
+
+    #[test]
+    fn a_wide_board_keeps_its_rows_apart() {
+        let mut rules = Rules::new();
+        rules.width = 12;
+        rules.height = 6;
+        let mut board = Board::new(&rules);
+
+        assert_eq!(board.fire_at(11, 5), FireResult::Miss);
+        assert!(board.get_cell(11, 5).unwrap().is_fired());
+        assert!(board.get_cell(5, 11).is_err());
+        assert_eq!(board.fire_at(12, 0), FireResult::OutOfBounds);
+        assert_eq!(board.fire_at(0, 6), FireResult::OutOfBounds);
+    }
+
+    #[test]
+    fn can_place_reports_each_error() {
+        let mut board = board(Adjacency::Allowed);
+        assert_eq!(
+            board.can_place(&ship("Carrier"), 8, 0, Orientation::Horizontal),
+            Err(PlacementError::OutOfBounds)
+        );
+        assert_eq!(
+            board.can_place(&ship("Carrier"), 0, u32::MAX, Orientation::Vertical),
+            Err(PlacementError::OutOfBounds)
+        );
+
+        board
+            .place(&ship("Destroyer"), 0, 0, Orientation::Horizontal)
+            .unwrap();
+        assert_eq!(
+            board.can_place(&ship("Destroyer"), 5, 5, Orientation::Horizontal),
+            Err(PlacementError::FleetQuotaExceeded)
+        );
+        assert_eq!(
+            board.can_place(&ship("Submarine"), 1, 0, Orientation::Vertical),
+            Err(PlacementError::Overlaps(ship("Destroyer")))
+        );
+        assert_eq!(
+            board.can_place(&ship("Submarine"), 0, 1, Orientation::Horizontal),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn can_place_follows_the_adjacency_rule() {
+        let mut no_orthogonal = board(Adjacency::NoOrthogonal);
+        let mut no_touching = board(Adjacency::NoTouching);
+        for board in [&mut no_orthogonal, &mut no_touching] {
+            board
+                .place(&ship("Destroyer"), 0, 0, Orientation::Horizontal)
+                .unwrap();
+            assert_eq!(
+                board.can_place(&ship("Submarine"), 0, 1, Orientation::Horizontal),
+                Err(PlacementError::TouchesAnotherShip)
+            );
         }
-        ship_types
+
+        // Only the diagonal touches
+        assert_eq!(
+            no_orthogonal.can_place(&ship("Submarine"), 2, 1, Orientation::Horizontal),
+            Ok(())
+        );
+        assert_eq!(
+            no_touching.can_place(&ship("Submarine"), 2, 1, Orientation::Horizontal),
+            Err(PlacementError::TouchesAnotherShip)
+        );
+    }
+
+    #[test]
+    fn parse_position_reads_labels() {
+        let board = board(Adjacency::Allowed);
+        assert_eq!(board.parse_position("B7"), Ok((1, 6)));
+        assert_eq!(board.parse_position(" j10 "), Ok((9, 9)));
+        assert_eq!(
+            board.parse_position(&Board::position_label(3, 4)),
+            Ok((3, 4))
+        );
+
+        assert_eq!(board.parse_position(""), Err(CoordinateError::Empty));
+        assert_eq!(board.parse_position("7B"), Err(CoordinateError::Malformed));
+        assert_eq!(board.parse_position("B"), Err(CoordinateError::Malformed));
+        assert_eq!(
+            board.parse_position("k1"),
+            Err(CoordinateError::ColumnOutOfRange("K".to_string()))
+        );
+        assert_eq!(
+            board.parse_position("ZZZZZZZZ1"),
+            Err(CoordinateError::ColumnOutOfRange("ZZZZZZZZ".to_string()))
+        );
+        assert_eq!(
+            board.parse_position("A0"),
+            Err(CoordinateError::RowOutOfRange("0".to_string()))
+        );
+        assert_eq!(
+            board.parse_position("A11"),
+            Err(CoordinateError::RowOutOfRange("11".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_placement_reads_a_direction() {
+        let board = board(Adjacency::Allowed);
+        assert_eq!(
+            board.parse_placement("B7 h"),
+            Ok((1, 6, Orientation::Horizontal))
+        );
+        assert_eq!(
+            board.parse_placement("c3 Vertical"),
+            Ok((2, 2, Orientation::Vertical))
+        );
+
+        assert_eq!(
+            board.parse_placement("B7"),
+            Err(CoordinateError::MissingOrientation)
+        );
+        assert_eq!(
+            board.parse_placement("B7 x"),
+            Err(CoordinateError::UnknownOrientation("x".to_string()))
+        );
+        assert_eq!(
+            board.parse_placement("B7 h v"),
+            Err(CoordinateError::TrailingText("v".to_string()))
+        );
+        assert_eq!(
+            board.parse_placement("Q1 h"),
+            Err(CoordinateError::ColumnOutOfRange("Q".to_string()))
+        );
+    }
+
+    #[test]
+    fn fire_at_reports_results() {
+        let mut board = board(Adjacency::Allowed);
+        board
+            .place(&ship("Destroyer"), 0, 0, Orientation::Horizontal)
+            .unwrap();
+
+        assert_eq!(board.fire_at(5, 5), FireResult::Miss);
+        assert_eq!(board.fire_at(5, 5), FireResult::AlreadyFired);
+        assert_eq!(board.fire_at(0, 0), FireResult::Hit);
+        assert_eq!(board.fire_at(1, 0), FireResult::Sunk(ship("Destroyer")));
+        assert_eq!(board.fire_at(10, 0), FireResult::OutOfBounds);
+        assert_eq!(board.fire_at(0, u32::MAX), FireResult::OutOfBounds);
     }
 }
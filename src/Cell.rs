@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Occupant {
+    Water,
+    Ship(usize),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum ShotResult {
+    Miss,
+    Hit,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Cell {
+    pub occupant: Occupant,
+    pub shot: Option<ShotResult>,
+}
+
+impl Cell {
+    pub fn new() -> Self {
+        Self {
+            occupant: Occupant::Water,
+            shot: None,
+        }
+    }
+
+    pub fn is_fired(&self) -> bool {
+        self.shot.is_some()
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
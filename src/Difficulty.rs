@@ -1,20 +1,26 @@
 use colored::Color;
 use num_derive::{FromPrimitive, ToPrimitive};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, FromPrimitive, ToPrimitive, Serialize, Deserialize)]
 pub enum Difficulty {
     Easy = 0,
     Medium = 1,
     Hard = 2,
+    Expert = 3,
 }
 
 impl Difficulty {
+    pub fn all() -> [Self; 4] {
+        [Self::Easy, Self::Medium, Self::Hard, Self::Expert]
+    }
+
     pub fn to_string(&self) -> &str {
         match self {
             Self::Easy => "Easy",
             Self::Medium => "Medium",
             Self::Hard => "Hard",
-            _ => "",
+            Self::Expert => "Expert",
         }
     }
 
@@ -23,6 +29,7 @@ impl Difficulty {
             Self::Easy => Color::Green,
             Self::Medium => Color::Yellow,
             Self::Hard => Color::Red,
+            Self::Expert => Color::Magenta,
         }
     }
 }
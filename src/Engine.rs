@@ -0,0 +1,484 @@
+use crate::{
+    Board::{Board, FireResult, Shot},
+    Fleet::FleetConfig,
+    Placement::PlacementStrategy,
+    Rules::Adjacency,
+    Ship::{Orientation, PlacedShip, ShipType},
+    Targeting::{TargetView, TargetingStrategy},
+};
+use rand::RngCore;
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+/// Bumped whenever a command changes, so engines can refuse versions they do not know.
+pub const ENGINE_PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum EngineError {
+    Launch(io::Error),
+    Io(io::Error),
+    Closed,
+    Unexpected(String),
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Launch(e) => write!(f, "Could not start the engine: {}", e),
+            Self::Io(e) => write!(f, "Could not talk to the engine: {}", e),
+            Self::Closed => write!(f, "The engine stopped"),
+            Self::Unexpected(line) => write!(f, "The engine sent an unexpected line: {}", line),
+        }
+    }
+}
+
+impl EngineError {
+    /// The error without its details, for callers whose errors are plain text.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Self::Launch(_) => "Could not start the engine",
+            Self::Io(_) => "Could not talk to the engine",
+            Self::Closed => "The engine stopped",
+            Self::Unexpected(_) => "The engine sent an unexpected line",
+        }
+    }
+}
+
+impl From<io::Error> for EngineError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// An external program that places ships and picks shots, spoken to over its
+/// standard input and output one line at a time.
+pub struct Engine {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Engine {
+    /// Starts `command`, split on whitespace into a program and its arguments,
+    /// and checks that it speaks the protocol.
+    pub fn launch(command: &str) -> Result<Self, EngineError> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or(EngineError::Launch(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no command given",
+        )))?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(EngineError::Launch)?;
+
+        let mut engine = Self {
+            name: program.to_string(),
+            stdin: child.stdin.take().unwrap(),
+            stdout: BufReader::new(child.stdout.take().unwrap()),
+            child,
+        };
+        engine.send(&format!("protocol {}", ENGINE_PROTOCOL_VERSION))?;
+        loop {
+            let line = engine.receive()?;
+            match line.split_once(' ').unwrap_or((&line, "")) {
+                ("ok", _) => return Ok(engine),
+                ("name", name) if !name.is_empty() => engine.name = name.to_string(),
+                _ => return Err(EngineError::Unexpected(line)),
+            }
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), EngineError> {
+        writeln!(self.stdin, "{}", line)?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    /// The next line the engine writes, skipping blank ones.
+    fn receive(&mut self) -> Result<String, EngineError> {
+        loop {
+            let mut line = String::new();
+            if self.stdout.read_line(&mut line)? == 0 {
+                return Err(EngineError::Closed);
+            }
+            let line = line.trim();
+            if !line.is_empty() {
+                return Ok(line.to_string());
+            }
+        }
+    }
+
+    /// Tells the engine the board and fleet of a new game.
+    pub fn new_game(
+        &mut self,
+        width: u32,
+        height: u32,
+        fleet: &FleetConfig,
+        adjacency: Adjacency,
+    ) -> Result<(), EngineError> {
+        let adjacency = match adjacency {
+            Adjacency::Allowed => "allowed",
+            Adjacency::NoOrthogonal => "no-orthogonal",
+            Adjacency::NoTouching => "no-touching",
+        };
+        self.send(&format!("newgame {} {} {}", width, height, adjacency))?;
+        for entry in fleet.get_entries() {
+            self.send(&format!(
+                "ship {} {} {}",
+                entry.ship_type.get_size(),
+                entry.count,
+                entry.ship_type.to_string()
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Asks where to put ships of the given sizes, in the same order.
+    pub fn place(&mut self, ships: &[ShipType]) -> Result<Vec<PlacedShip>, EngineError> {
+        let sizes: Vec<String> = ships
+            .iter()
+            .map(|ship_type| ship_type.get_size().to_string())
+            .collect();
+        self.send(&format!("place {}", sizes.join(" ")))?;
+
+        let mut placed = Vec::new();
+        for ship_type in ships {
+            placed.push(parse_ship(&self.receive()?, ship_type)?);
+        }
+        Ok(placed)
+    }
+
+    /// Asks for the next `count` shots.
+    pub fn fire(&mut self, count: u32) -> Result<Vec<(u32, u32)>, EngineError> {
+        self.send(&format!("fire {}", count))?;
+
+        let mut targets = Vec::new();
+        for _ in 0..count {
+            targets.push(parse_shot(&self.receive()?)?);
+        }
+        Ok(targets)
+    }
+
+    /// Tells the engine how one of its shots landed.
+    pub fn report(&mut self, shot: &Shot) -> Result<(), EngineError> {
+        self.send(&result_line(shot))
+    }
+}
+
+/// Reads a `ship <x> <y> <h|v>` answer for a ship of `ship_type`.
+fn parse_ship(line: &str, ship_type: &ShipType) -> Result<PlacedShip, EngineError> {
+    let unexpected = || EngineError::Unexpected(line.to_string());
+    let ["ship", x, y, orientation] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+        return Err(unexpected());
+    };
+    let orientation = match orientation {
+        "h" => Orientation::Horizontal,
+        "v" => Orientation::Vertical,
+        _ => return Err(unexpected()),
+    };
+    let (Ok(x), Ok(y)) = (x.parse(), y.parse()) else {
+        return Err(unexpected());
+    };
+    Ok(PlacedShip::new(ship_type.clone(), x, y, orientation))
+}
+
+/// Reads a `shot <x> <y>` answer.
+fn parse_shot(line: &str) -> Result<(u32, u32), EngineError> {
+    let unexpected = || EngineError::Unexpected(line.to_string());
+    let ["shot", x, y] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+        return Err(unexpected());
+    };
+    match (x.parse(), y.parse()) {
+        (Ok(x), Ok(y)) => Ok((x, y)),
+        _ => Err(unexpected()),
+    }
+}
+
+/// The `result` line telling an engine how `shot` landed.
+fn result_line(shot: &Shot) -> String {
+    match &shot.result {
+        FireResult::Sunk(ship_type) => {
+            format!("result {} {} sunk {}", shot.x, shot.y, ship_type.get_size())
+        }
+        FireResult::Miss => format!("result {} {} miss", shot.x, shot.y),
+        _ => format!("result {} {} hit", shot.x, shot.y),
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Fires where an external engine says. The engine is started on the first
+/// shot. If it fails, or fires where it can not, `error` says why and it is
+/// not asked again.
+pub struct EngineStrategy {
+    command: String,
+    engine: Option<Engine>,
+    error: Option<&'static str>,
+    /// How many of the shots so far the engine has been told about.
+    reported: usize,
+}
+
+impl EngineStrategy {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            engine: None,
+            error: None,
+            reported: 0,
+        }
+    }
+
+    /// Tells the engine about every shot fired since it was last asked.
+    fn catch_up(&mut self, view: &TargetView) -> Result<&mut Engine, EngineError> {
+        if self.engine.is_none() {
+            let mut engine = Engine::launch(&self.command)?;
+            engine.new_game(
+                view.get_width(),
+                view.get_height(),
+                view.get_fleet(),
+                view.get_adjacency(),
+            )?;
+            self.engine = Some(engine);
+        }
+        let engine = self.engine.as_mut().unwrap();
+
+        for shot in &view.get_shots()[self.reported..] {
+            engine.report(shot)?;
+        }
+        self.reported = view.get_shots().len();
+        Ok(engine)
+    }
+
+    fn ask(&mut self, view: &TargetView, count: u32) -> Option<Vec<(u32, u32)>> {
+        if self.error.is_some() {
+            return None;
+        }
+        let targets = match self.catch_up(view).and_then(|engine| engine.fire(count)) {
+            Ok(targets) => targets,
+            Err(e) => {
+                self.error = Some(e.reason());
+                self.engine = None;
+                return None;
+            }
+        };
+
+        let mut chosen = view.clone();
+        for (x, y) in &targets {
+            if !chosen.is_unknown(*x, *y) {
+                self.error =
+                    Some("The engine fired at a cell that is off the board or already fired at");
+                self.engine = None;
+                return None;
+            }
+            chosen.mark_targeted(*x, *y);
+        }
+        Some(targets)
+    }
+}
+
+impl TargetingStrategy for EngineStrategy {
+    fn next_target(&mut self, view: &TargetView, _rng: &mut dyn RngCore) -> (u32, u32) {
+        // The AI looks at `error` before firing at this
+        self.ask(view, 1).map_or((0, 0), |targets| targets[0])
+    }
+
+    fn next_salvo(
+        &mut self,
+        view: &TargetView,
+        count: u32,
+        _rng: &mut dyn RngCore,
+    ) -> Vec<(u32, u32)> {
+        self.ask(view, count).unwrap_or_default()
+    }
+
+    fn error(&self) -> Option<&'static str> {
+        self.error
+    }
+}
+
+/// Places the fleet where an external engine says.
+pub struct EnginePlacement {
+    command: String,
+}
+
+impl EnginePlacement {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+        }
+    }
+
+    fn ask(&self, board: &mut Board) -> Result<(), EngineError> {
+        let mut engine = Engine::launch(&self.command)?;
+        engine.new_game(
+            board.get_width(),
+            board.get_height(),
+            board.get_fleet(),
+            board.get_adjacency(),
+        )?;
+        for ship in engine.place(&board.remaining_fleet())? {
+            board
+                .place(&ship.ship_type, ship.x, ship.y, ship.orientation)
+                .map_err(|e| EngineError::Unexpected(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+impl PlacementStrategy for EnginePlacement {
+    fn weight(&mut self, _board: &Board, _cells: &[(u32, u32)]) -> f64 {
+        1.0
+    }
+
+    fn place_fleet(
+        &mut self,
+        board: &mut Board,
+        _rng: &mut dyn RngCore,
+    ) -> Result<(), &'static str> {
+        let start = board.clone();
+        self.ask(board).map_err(|e| {
+            *board = start;
+            e.reason()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        GameState::{GameRng, GameState, Player},
+        Rules::Rules,
+        Simulation::{self, Contestant},
+        Targeting::StrategyRegistry,
+    };
+    use rand::SeedableRng;
+    use std::fs;
+
+    const MISSING: &str = "./no-such-engine";
+
+    /// An engine that fires along the rows from the top left and writes
+    /// every line it is sent to `log`.
+    const RECORDING_ENGINE: &str = "n=0
+while read command rest; do
+  echo \"$command $rest\" >> \"$1\"
+  case $command in
+    protocol) echo ok ;;
+    fire) for i in $(seq $rest); do echo \"shot $((n % 10)) $((n / 10))\"; n=$((n + 1)); done ;;
+  esac
+done
+";
+
+    #[test]
+    fn engine_answers_are_read_strictly() {
+        let destroyer = ShipType::new("Destroyer", "des", 2);
+        assert_eq!(
+            parse_ship("ship 3 4 v", &destroyer).unwrap(),
+            PlacedShip::new(destroyer.clone(), 3, 4, Orientation::Vertical)
+        );
+        for line in ["ship 3 4", "ship 3 4 d", "ship -1 4 h", "shot 3 4 h"] {
+            assert!(parse_ship(line, &destroyer).is_err());
+        }
+
+        assert_eq!(parse_shot("  shot 3   4 ").unwrap(), (3, 4));
+        for line in ["shot 3", "shot 3 4 5", "shot a 4", "ship 3 4"] {
+            assert!(parse_shot(line).is_err());
+        }
+    }
+
+    #[test]
+    fn engines_hear_each_result_with_the_ship_it_sank() {
+        let dir = std::env::temp_dir();
+        let script = dir.join(format!("battleships-engine-{}.sh", std::process::id()));
+        let log = dir.join(format!("battleships-engine-{}.log", std::process::id()));
+        fs::write(&script, RECORDING_ENGINE).unwrap();
+        let _ = fs::remove_file(&log);
+
+        let mut state = GameState::with_seed(Rules::new(), 7).unwrap();
+        let command = format!("sh {} {}", script.display(), log.display());
+        state.registry_mut().register_engine("recording", &command);
+        state.set_strategy("recording").unwrap();
+        state.set_first_player(Player::Ai).unwrap();
+        let destroyer = state.fleet().ships()[0].clone();
+        state
+            .place_ship(&destroyer, 0, 0, Orientation::Horizontal)
+            .unwrap();
+        state.auto_place("uniform").unwrap();
+        state.finish_setup().unwrap();
+
+        // The engine hears how a shot landed when it is next asked to fire
+        for x in 0..3 {
+            state.ai_turn().unwrap();
+            state.fire(x, 9).unwrap();
+        }
+        state.ai_turn().unwrap();
+        let expected: Vec<String> = state.player_board().get_shots()[..3]
+            .iter()
+            .map(result_line)
+            .collect();
+        drop(state);
+
+        let heard: Vec<String> = fs::read_to_string(&log)
+            .unwrap()
+            .lines()
+            .filter(|line| line.starts_with("result"))
+            .map(|line| line.trim().to_string())
+            .collect();
+        fs::remove_file(&script).unwrap();
+        fs::remove_file(&log).unwrap();
+
+        assert_eq!(heard, expected);
+        assert_eq!(heard[1], "result 1 0 sunk 2");
+    }
+
+    #[test]
+    fn a_missing_engine_stops_the_game() {
+        let mut state = GameState::with_seed(Rules::new(), 7).unwrap();
+        state.registry_mut().register_engine("missing", MISSING);
+        state.set_placement("missing").unwrap();
+        state.auto_place("uniform").unwrap();
+        assert_eq!(state.finish_setup(), Err("Could not start the engine"));
+
+        state.set_placement("uniform").unwrap();
+        state.set_strategy("missing").unwrap();
+        state.set_first_player(Player::Ai).unwrap();
+        state.finish_setup().unwrap();
+        assert_eq!(state.ai_turn(), Err("Could not start the engine"));
+        assert_eq!(state.player_board().get_shots().len(), 0);
+    }
+
+    #[test]
+    fn a_missing_engine_stops_the_simulation() {
+        let mut registry = StrategyRegistry::new();
+        registry.register_engine("missing", MISSING);
+        let contestants = [
+            Contestant::new("missing", "missing", "uniform"),
+            Contestant::new("random", "random", "uniform"),
+        ];
+        let result = Simulation::play_game(
+            &Rules::new(),
+            &registry,
+            &contestants,
+            0,
+            &mut GameRng::seed_from_u64(7),
+        );
+        assert_eq!(result.err(), Some("Could not start the engine"));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{Rules::MAX_BOARD_SIZE, Ship::ShipType};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct FleetEntry {
+    pub ship_type: ShipType,
+    pub count: u32,
+}
+
+/// Fleets read from save files or the network go through the same checks as
+/// `FleetConfig::new`.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedFleet")]
+pub struct FleetConfig {
+    name: String,
+    entries: Vec<FleetEntry>,
+}
+
+#[derive(Deserialize)]
+struct UncheckedFleet {
+    name: String,
+    entries: Vec<FleetEntry>,
+}
+
+impl TryFrom<UncheckedFleet> for FleetConfig {
+    type Error = &'static str;
+
+    fn try_from(fleet: UncheckedFleet) -> Result<Self, Self::Error> {
+        Self::new(&fleet.name, fleet.entries)
+    }
+}
+
+impl FleetConfig {
+    pub fn new(name: &str, entries: Vec<FleetEntry>) -> Result<Self, &'static str> {
+        if entries.iter().all(|entry| entry.count == 0) {
+            return Err("The fleet needs at least one ship");
+        }
+        let mut total_cells: u32 = 0;
+        for (i, entry) in entries.iter().enumerate() {
+            if entry.ship_type.get_size() == 0 {
+                return Err("Ships need a length of at least 1");
+            }
+            // Checked, since fleets can come from another player over the network
+            total_cells = entry
+                .count
+                .checked_mul(entry.ship_type.get_size())
+                .and_then(|cells| total_cells.checked_add(cells))
+                .filter(|cells| *cells <= MAX_BOARD_SIZE * MAX_BOARD_SIZE)
+                .ok_or("The fleet would not fit on the largest board")?;
+            let label_length = entry.ship_type.to_shorten().chars().count();
+            if label_length == 0 || label_length > 3 {
+                return Err("Ship labels must be 1 to 3 characters long");
+            }
+            if entries[..i]
+                .iter()
+                .any(|other| other.ship_type.to_string() == entry.ship_type.to_string())
+            {
+                return Err("Ship names must be unique within a fleet");
+            }
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            entries,
+        })
+    }
+
+    /// The Milton Bradley fleet the game originally shipped with.
+    pub fn classic() -> Self {
+        Self::new(
+            "Classic",
+            vec![
+                FleetEntry {
+                    ship_type: ShipType::new("Destroyer", "des", 2),
+                    count: 1,
+                },
+                FleetEntry {
+                    ship_type: ShipType::new("Submarine", "sub", 3),
+                    count: 1,
+                },
+                FleetEntry {
+                    ship_type: ShipType::new("Cruiser", "cru", 3),
+                    count: 1,
+                },
+                FleetEntry {
+                    ship_type: ShipType::new("Battleship", "bat", 4),
+                    count: 1,
+                },
+                FleetEntry {
+                    ship_type: ShipType::new("Carrier", "car", 5),
+                    count: 1,
+                },
+            ],
+        )
+        .unwrap()
+    }
+
+    /// The 1990 Hasbro fleet, which swaps the cruiser for a patrol boat.
+    pub fn hasbro_1990() -> Self {
+        Self::new(
+            "Hasbro 1990",
+            vec![
+                FleetEntry {
+                    ship_type: ShipType::new("Patrol Boat", "pat", 2),
+                    count: 1,
+                },
+                FleetEntry {
+                    ship_type: ShipType::new("Submarine", "sub", 3),
+                    count: 1,
+                },
+                FleetEntry {
+                    ship_type: ShipType::new("Destroyer", "des", 3),
+                    count: 1,
+                },
+                FleetEntry {
+                    ship_type: ShipType::new("Battleship", "bat", 4),
+                    count: 1,
+                },
+                FleetEntry {
+                    ship_type: ShipType::new("Carrier", "car", 5),
+                    count: 1,
+                },
+            ],
+        )
+        .unwrap()
+    }
+
+    pub fn presets() -> Vec<Self> {
+        vec![Self::classic(), Self::hasbro_1990()]
+    }
+
+    pub fn to_string(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_entries(&self) -> &Vec<FleetEntry> {
+        &self.entries
+    }
+
+    pub fn get_count(&self, ship_type: &ShipType) -> u32 {
+        self.entries
+            .iter()
+            .filter(|entry| entry.ship_type == *ship_type)
+            .map(|entry| entry.count)
+            .sum()
+    }
+
+    pub fn total_ships(&self) -> u32 {
+        self.entries.iter().map(|entry| entry.count).sum()
+    }
+
+    pub fn total_cells(&self) -> u32 {
+        self.entries
+            .iter()
+            .map(|entry| entry.count * entry.ship_type.get_size())
+            .sum()
+    }
+
+    /// Every ship in the fleet, with duplicates repeated once per count.
+    pub fn ships(&self) -> Vec<ShipType> {
+        self.entries
+            .iter()
+            .flat_map(|entry| (0..entry.count).map(|_| entry.ship_type.clone()))
+            .collect()
+    }
+}
+
+impl Default for FleetConfig {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, label: &str, size: u32, count: u32) -> FleetEntry {
+        FleetEntry {
+            ship_type: ShipType::new(name, label, size),
+            count,
+        }
+    }
+
+    #[test]
+    fn new_rejects_impossible_fleets() {
+        assert!(FleetConfig::new("Empty", vec![]).is_err());
+        assert!(FleetConfig::new("None", vec![entry("Raft", "raf", 1, 0)]).is_err());
+        assert!(FleetConfig::new("Flat", vec![entry("Raft", "raf", 0, 1)]).is_err());
+        assert!(FleetConfig::new("Long", vec![entry("Raft", "raft", 1, 1)]).is_err());
+        assert!(FleetConfig::new("Armada", vec![entry("Raft", "raf", 1, 4_000_000_000)]).is_err());
+        assert!(FleetConfig::new(
+            "Overflow",
+            vec![
+                entry("Raft", "raf", 1, u32::MAX),
+                entry("Barge", "bar", 1, u32::MAX)
+            ]
+        )
+        .is_err());
+        assert!(FleetConfig::new(
+            "Twins",
+            vec![entry("Raft", "raf", 1, 1), entry("Raft", "rft", 2, 1)]
+        )
+        .is_err());
+        assert!(FleetConfig::new("Raft", vec![entry("Raft", "raf", 1, 2)]).is_ok());
+    }
+
+    #[test]
+    fn deserializing_runs_the_same_checks() {
+        let json = serde_json::to_string(&FleetConfig::classic()).unwrap();
+        assert_eq!(
+            serde_json::from_str::<FleetConfig>(&json).unwrap(),
+            FleetConfig::classic()
+        );
+        assert!(
+            serde_json::from_str::<FleetConfig>(&json.replace("\"size\":2", "\"size\":0")).is_err()
+        );
+        assert!(serde_json::from_str::<FleetConfig>(r#"{"name":"Empty","entries":[]}"#).is_err());
+        assert!(serde_json::from_str::<FleetConfig>(
+            &json.replace("\"count\":1", "\"count\":4000000000")
+        )
+        .is_err());
+    }
+}
@@ -1,331 +1,676 @@
-use std::char::from_u32;
-
+use battleships::{
+    Board::{Board, FireResult, PlacementError, Selection, Shot},
+    Difficulty::Difficulty,
+    Engine::{Engine, EngineError},
+    Fleet::FleetConfig,
+    GameState::{GameState, Opponent, Phase, Player},
+    Rules::{Adjacency, FireAgain, FiringMode, Rules, MAX_BOARD_SIZE, MIN_BOARD_SIZE},
+    Save::{self, SaveError, DEFAULT_SAVE_FILE},
+    Ship::{Orientation, PlacedShip, ShipType},
+};
 use colored::{Color, Colorize};
 use getch_rs::{Getch, Key};
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use crate::{Board::Board, Difficulty::Difficulty, Ship::ShipType, AI::AI};
+/// The name an engine played against from the command line is registered under.
+const ENGINE: &str = "engine";
 
 pub struct Game {
-    player_board: Board,
-    ai_board: Board,
-    guess_board: Board,
-    ai: AI,
-    game_difficulty: Difficulty,
+    state: GameState,
+    save_path: PathBuf,
+    /// Whether the AI was picked before the game started, so there is no
+    /// opponent or difficulty to choose.
+    ai_chosen: bool,
 }
 
 impl Game {
     pub fn new() -> Self {
         Self {
-            player_board: Board::new(None),
-            ai_board: Board::new(None),
-            guess_board: Board::new(None),
-            ai: AI::new(),
-            game_difficulty: Difficulty::Easy,
+            state: GameState::new(),
+            save_path: PathBuf::from(DEFAULT_SAVE_FILE),
+            ai_chosen: false,
+        }
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            state: GameState::with_seed(Rules::new(), seed).unwrap(),
+            save_path: PathBuf::from(DEFAULT_SAVE_FILE),
+            ai_chosen: false,
         }
     }
 
+    /// A game against a player over the network, who places their own fleet
+    /// on their own computer. `first` fires first.
+    pub fn online(rules: Rules, first: Player) -> Result<Self, &'static str> {
+        let mut state = GameState::with_rules(rules)?;
+        state.set_opponent(Opponent::Remote)?;
+        state.set_first_player(first)?;
+        Ok(Self {
+            state,
+            save_path: PathBuf::from(DEFAULT_SAVE_FILE),
+            ai_chosen: false,
+        })
+    }
+
+    /// A game against an external engine, started with `command`.
+    pub fn with_engine(command: &str) -> Result<Self, EngineError> {
+        Engine::launch(command)?;
+
+        let mut game = Self::new();
+        game.state.registry_mut().register_engine(ENGINE, command);
+        game.state.set_strategy(ENGINE).unwrap();
+        game.state.set_placement(ENGINE).unwrap();
+        game.ai_chosen = true;
+        Ok(game)
+    }
+
+    pub fn load(save_path: PathBuf) -> Result<Self, SaveError> {
+        Ok(Self {
+            state: Save::load_game(&save_path)?,
+            save_path,
+            ai_chosen: false,
+        })
+    }
+
     pub fn start_game(&mut self) {
-        self.set_difficulty();
+        if !self.ai_chosen {
+            self.set_opponent();
+            if self.state.opponent() == Opponent::Ai {
+                self.set_difficulty();
+            }
+        }
+        self.choose_rules();
+        self.continue_game();
+    }
+
+    pub fn choose_rules(&mut self) {
+        self.set_fleet();
+        self.set_adjacency();
+        self.set_firing_mode();
+        self.set_fire_again();
+        self.set_board_size();
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    pub fn state_mut(&mut self) -> &mut GameState {
+        &mut self.state
+    }
+
+    pub fn continue_game(&mut self) {
+        let two_player = self.state.opponent() == Opponent::Human;
+        while self.state.phase() == Phase::Setup {
+            if two_player {
+                self.pass_keyboard(&[]);
+            }
+            self.setup_board();
+        }
+        if self.state.phase() == Phase::Playing {
+            self.play_game();
+        }
+        if self.state.phase() == Phase::Finished {
+            self.show_result();
+        }
+    }
+
+    fn save_game(&self) -> String {
+        match Save::save_game(&self.state, &self.save_path) {
+            Ok(()) => format!("Game saved to {}", self.save_path.display())
+                .color(Color::Green)
+                .to_string(),
+            Err(e) => e.to_string().color(Color::Red).to_string(),
+        }
+    }
+
+    fn set_opponent(&mut self) {
+        self.choose(
+            "Opponent",
+            &Opponent::all(),
+            |opponent| opponent.to_string().color(Color::Blue).to_string(),
+            |state, opponent| state.set_opponent(*opponent),
+        );
+    }
+
+    /// Hides the boards until the player whose turn it is presses Enter, so
+    /// the other player can hand over the keyboard without seeing their fleet.
+    fn pass_keyboard(&self, messages: &[String]) {
+        let g = Getch::new();
+
+        Self::clear_scrollback();
+        for message in messages {
+            println!("{}", message);
+        }
+        println!();
+        println!(
+            "Pass the keyboard to {}, then press Enter",
+            self.player_name(self.state.turn()).color(Color::Blue)
+        );
+        loop {
+            match g.getch() {
+                Ok(Key::Char('\r')) => break,
+                Ok(_) => (),
+                Err(e) => println!("{}", e),
+            }
+        }
+        Self::clear_scrollback();
+    }
+
+    /// Clears the screen along with the terminal's scrollback, so the board
+    /// printed before a hand-over cannot be scrolled back to.
+    fn clear_scrollback() {
+        print!("\x1b[3J\x1b[2J\x1b[H");
+        let _ = io::stdout().flush();
+    }
+
+    /// What a player is called on screen.
+    fn player_name(&self, player: Player) -> &'static str {
+        self.state.opponent().player_name(player)
+    }
+
+    /// Describes a shot fired by `shooter` at the other player's fleet.
+    pub fn describe(&self, shooter: Player, shot: Shot) -> String {
+        let owner = match (self.state.opponent(), shooter) {
+            (Opponent::Ai, Player::Human) => "their",
+            (Opponent::Ai, Player::Ai) => "your",
+            (Opponent::Human, Player::Human) => "Player 2's",
+            (Opponent::Human, Player::Ai) => "Player 1's",
+            (Opponent::Remote, Player::Human) => "their",
+            (Opponent::Remote, Player::Ai) => "your",
+        };
+        Self::describe_shot(self.player_name(shooter), owner, shot)
     }
 
     fn set_difficulty(&mut self) {
-        let mut current_difficulty = <Difficulty as num::FromPrimitive>::from_u32(0).unwrap();
+        self.choose(
+            "Current Difficulty",
+            &Difficulty::all(),
+            |difficulty| {
+                difficulty
+                    .to_string()
+                    .color(difficulty.get_color())
+                    .to_string()
+            },
+            |state, difficulty| state.set_difficulty(*difficulty),
+        );
+    }
+
+    fn set_fleet(&mut self) {
+        self.choose(
+            "Current Fleet",
+            &FleetConfig::presets(),
+            |fleet| {
+                let mut text = fleet.to_string().color(Color::Blue).to_string();
+                for entry in fleet.get_entries() {
+                    text += &format!(
+                        "\n{} x{} ({} long)",
+                        entry.ship_type.to_string(),
+                        entry.count,
+                        entry.ship_type.get_size()
+                    );
+                }
+                text
+            },
+            |state, fleet| {
+                let mut rules = state.rules().clone();
+                rules.fleet = fleet.clone();
+                state.set_rules(rules)
+            },
+        );
+    }
+
+    fn set_adjacency(&mut self) {
+        self.choose(
+            "Ship Spacing",
+            &Adjacency::all(),
+            |adjacency| adjacency.to_string().color(Color::Blue).to_string(),
+            |state, adjacency| {
+                let mut rules = state.rules().clone();
+                rules.adjacency = *adjacency;
+                state.set_rules(rules)
+            },
+        );
+    }
+
+    fn set_firing_mode(&mut self) {
+        self.choose(
+            "Shots",
+            &FiringMode::all(),
+            |firing| firing.to_string().color(Color::Blue).to_string(),
+            |state, firing| {
+                let mut rules = state.rules().clone();
+                rules.firing = *firing;
+                state.set_rules(rules)
+            },
+        );
+    }
+
+    fn set_fire_again(&mut self) {
+        self.choose(
+            "Hits",
+            &FireAgain::all(),
+            |fire_again| fire_again.to_string().color(Color::Blue).to_string(),
+            |state, fire_again| {
+                let mut rules = state.rules().clone();
+                rules.fire_again = *fire_again;
+                state.set_rules(rules)
+            },
+        );
+    }
+
+    /// Lets the player step through `options` with the arrow keys until one
+    /// is confirmed and `apply` accepts it. `describe` gives the text shown
+    /// after `title` for an option.
+    fn choose<T>(
+        &mut self,
+        title: &str,
+        options: &[T],
+        describe: impl Fn(&T) -> String,
+        apply: impl Fn(&mut GameState, &T) -> Result<(), &'static str>,
+    ) {
+        let mut current_option: usize = 0;
+        let mut error: Option<&'static str> = None;
+        let mut finished = false;
+
+        let g = Getch::new();
+
+        while !finished {
+            println!("{}: {}", title, describe(&options[current_option]));
+            if let Some(e) = error {
+                println!("{}", e.color(Color::Red));
+            }
+            println!();
+
+            println!("Change Selection: Arrow Keys");
+            println!("Confirm Selection: Enter Key");
+
+            match g.getch() {
+                Ok(Key::Left) => {
+                    current_option = current_option.saturating_sub(1);
+                }
+                Ok(Key::Right) => {
+                    if current_option < options.len() - 1 {
+                        current_option += 1;
+                    }
+                }
+                Ok(Key::Char('\r')) => match apply(&mut self.state, &options[current_option]) {
+                    Ok(()) => finished = true,
+                    Err(e) => error = Some(e),
+                },
+                Ok(_) => (),
+                Err(e) => println!("{}", e),
+            }
+            print!("{}[2J", 27 as char);
+        }
+    }
+
+    fn set_board_size(&mut self) {
+        let mut rules = self.state.rules().clone();
         let mut finished = false;
 
         let g = Getch::new();
 
         while !finished {
-            let current_difficulty_int = num::ToPrimitive::to_u32(&current_difficulty).unwrap();
+            let valid = rules.validate();
+            let color = if valid.is_ok() {
+                Color::Green
+            } else {
+                Color::Red
+            };
             println!(
-                "Current Difficulty: {}",
-                current_difficulty
-                    .to_string()
-                    .color(current_difficulty.get_color())
+                "Board Size: {}",
+                format!("{}x{}", rules.width, rules.height).color(color)
             );
+            if let Err(e) = valid {
+                println!("{}", e.color(Color::Red));
+            }
+            println!();
 
-            println!("Change Selection: Arrow Keys");
+            println!("Change Width: Left/Right Arrow Keys");
+            println!("Change Height: Up/Down Arrow Keys");
             println!("Confirm Selection: Enter Key");
 
             match g.getch() {
                 Ok(Key::Left) => {
-                    if current_difficulty_int > 0 {
-                        current_difficulty = <Difficulty as num::FromPrimitive>::from_u32(
-                            current_difficulty_int - 1,
-                        )
-                        .unwrap();
+                    if rules.width > MIN_BOARD_SIZE {
+                        rules.width -= 1;
                     }
                 }
                 Ok(Key::Right) => {
-                    if current_difficulty_int < 2 {
-                        current_difficulty = <Difficulty as num::FromPrimitive>::from_u32(
-                            current_difficulty_int + 1,
-                        )
-                        .unwrap();
+                    if rules.width < MAX_BOARD_SIZE {
+                        rules.width += 1;
+                    }
+                }
+                Ok(Key::Down) => {
+                    if rules.height > MIN_BOARD_SIZE {
+                        rules.height -= 1;
+                    }
+                }
+                Ok(Key::Up) => {
+                    if rules.height < MAX_BOARD_SIZE {
+                        rules.height += 1;
                     }
                 }
                 Ok(Key::Char('\r')) => {
-                    self.game_difficulty = current_difficulty;
-                    finished = true;
+                    finished = self.state.set_rules(rules.clone()).is_ok();
                 }
                 Ok(_) => (),
                 Err(e) => println!("{}", e),
             }
             print!("{}[2J", 27 as char);
         }
-
-        self.setup_board();
     }
 
-    fn setup_board(&mut self) {
+    pub fn setup_board(&mut self) {
         let g = Getch::new();
 
-        self.ai.place_ships(&mut self.ai_board);
-
         let mut x: u32 = 0;
         let mut y: u32 = 0;
 
         let mut select_mode = true;
+        let mut pick_mode = false;
+        let mut lifted: Option<PlacedShip> = None;
 
-        let mut remaining_ships = vec![0, 1, 1, 1, 1, 1];
-
-        let mut horizontal = true;
+        let mut orientation = Orientation::Horizontal;
 
         let mut fininished = false;
 
-        let mut current_ship_type = <ShipType as num::FromPrimitive>::from_u32(1).unwrap();
+        let mut message: Option<String> = None;
+
+        let ship_types: Vec<ShipType> = self
+            .state
+            .fleet()
+            .get_entries()
+            .iter()
+            .map(|entry| entry.ship_type.clone())
+            .collect();
+        let mut current_ship_type_int: usize = 0;
+
+        let placements: Vec<String> = self
+            .state
+            .registry()
+            .placement_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let mut current_placement_int: usize = 0;
 
-        let mut previous_ship_types: Vec<ShipType> = Vec::new();
+        let two_player = self.state.opponent() == Opponent::Human;
 
         while !fininished {
-            let current_ship_type_int = num::ToPrimitive::to_usize(&current_ship_type).unwrap();
+            if two_player {
+                println!(
+                    "{}",
+                    format!("{}'s Fleet", self.player_name(self.state.turn())).color(Color::Blue)
+                );
+            }
+            let current_ship_type = &ship_types[current_ship_type_int];
+            let remaining = self.state.remaining_ships(current_ship_type);
+            let width = self.state.board(self.state.turn()).get_width();
+            let height = self.state.board(self.state.turn()).get_height();
+            let ship_size = current_ship_type.get_size();
+
             let mut color = Color::White;
-            if remaining_ships[current_ship_type_int] <= 0 {
+            if remaining == 0 {
                 color = Color::Red;
             } else if select_mode {
                 color = Color::Green;
             }
-            println!("{}", current_ship_type.to_string().color(color));
+            if pick_mode {
+                println!("{}", "Pick a ship to move".color(Color::Green));
+            } else {
+                println!(
+                    "{} ({} left)",
+                    current_ship_type.to_string().color(color),
+                    remaining
+                );
+            }
 
             println!(
                 "{} {}",
-                ((65 + x) as u8 as char).to_string().color(Color::Blue),
+                Board::column_label(x).color(Color::Blue),
                 (y + 1).to_string().color(Color::Yellow)
             );
 
-            self.player_board.show();
+            let selection = match select_mode {
+                true if pick_mode => Selection::Cursor { x, y },
+                true => Selection::None,
+                false => Selection::Ship(PlacedShip::new(
+                    current_ship_type.clone(),
+                    x,
+                    y,
+                    orientation,
+                )),
+            };
+            self.state.board(self.state.turn()).show(true, &selection);
 
             println!();
 
+            if !select_mode {
+                if let Err(e) = self
+                    .state
+                    .can_place_ship(current_ship_type, x, y, orientation)
+                {
+                    println!("{}", e.to_string().color(Color::Red));
+                }
+            }
+            if let Some(message) = message.take() {
+                println!("{}", message);
+            }
+
             println!("Change/Move Selection: Arrow Keys");
             println!("Rotate: R Key");
             println!("Confirm Selection: Enter Key");
+            println!(
+                "Auto-Place Remaining Ships: A Key ({}, change with P Key)",
+                placements[current_placement_int].color(Color::Blue)
+            );
+            println!("Type Where To Place The Ship: T Key");
+            println!("Move A Placed Ship: M Key");
+            println!("New Layout For The Whole Fleet: N Key");
+            println!("Clear All Ships: C Key");
+            println!("Save Game: S Key");
             println!("Go Back/Finish: Escape Key");
 
             let c = g.getch();
 
-            if select_mode {
+            if let Ok(Key::Char('s')) = c {
+                // The lifted ship is off the board and out of the log
+                message = Some(match lifted {
+                    Some(_) => "Put the ship down before saving"
+                        .color(Color::Red)
+                        .to_string(),
+                    None => self.save_game(),
+                });
+            }
+
+            if pick_mode {
                 match c {
                     Ok(Key::Left) => {
-                        if current_ship_type_int > 1 {
-                            current_ship_type = <ShipType as num::FromPrimitive>::from_usize(
-                                current_ship_type_int - 1,
-                            )
-                            .unwrap();
+                        x = x.saturating_sub(1);
+                    }
+                    Ok(Key::Right) => {
+                        if x < width - 1 {
+                            x += 1;
                         }
                     }
+                    Ok(Key::Up) => {
+                        y = y.saturating_sub(1);
+                    }
+                    Ok(Key::Down) => {
+                        if y < height - 1 {
+                            y += 1;
+                        }
+                    }
+                    Ok(Key::Char('\r')) => match self.state.lift_ship(x, y) {
+                        Ok(ship) => {
+                            current_ship_type_int = ship_types
+                                .iter()
+                                .position(|ship_type| *ship_type == ship.ship_type)
+                                .unwrap_or(0);
+                            x = ship.x;
+                            y = ship.y;
+                            orientation = ship.orientation;
+                            lifted = Some(ship);
+                            pick_mode = false;
+                            select_mode = false;
+                        }
+                        Err(e) => message = Some(e.color(Color::Red).to_string()),
+                    },
+                    Ok(Key::Esc) => {
+                        pick_mode = false;
+                    }
+                    Ok(_) => (),
+                    Err(e) => println!("{}", e),
+                }
+            } else if select_mode {
+                match c {
+                    Ok(Key::Left) => {
+                        current_ship_type_int = current_ship_type_int.saturating_sub(1);
+                    }
                     Ok(Key::Right) => {
-                        if current_ship_type_int < 5 {
-                            current_ship_type = <ShipType as num::FromPrimitive>::from_usize(
-                                current_ship_type_int + 1,
-                            )
-                            .unwrap();
+                        if current_ship_type_int < ship_types.len() - 1 {
+                            current_ship_type_int += 1;
                         }
                     }
                     Ok(Key::Char('\r')) => {
-                        if remaining_ships[current_ship_type_int] > 0 {
+                        if remaining > 0 {
                             select_mode = false;
-                            previous_ship_types = self.player_board.get_ship_types(
-                                x,
-                                y,
-                                current_ship_type.get_size(),
-                                horizontal,
-                            );
-                            self.player_board.set_ship_type(
-                                x,
-                                y,
-                                current_ship_type,
-                                current_ship_type.get_size(),
-                                horizontal,
-                            );
+                            if orientation == Orientation::Horizontal && ship_size > width {
+                                orientation = Orientation::Vertical;
+                            } else if orientation == Orientation::Vertical && ship_size > height {
+                                orientation = Orientation::Horizontal;
+                            }
                         }
                     }
-                    Ok(Key::Esc) => {
-                        fininished = true;
-                        for i in 0..6 {
-                            if remaining_ships[i] > 0 {
-                                fininished = false;
-                                break;
+                    Ok(Key::Char('a')) => {
+                        if let Err(e) = self.state.auto_place(&placements[current_placement_int]) {
+                            message = Some(e.color(Color::Red).to_string());
+                        }
+                    }
+                    Ok(Key::Char('n')) => {
+                        if let Err(e) = self.state.reroll_ships(&placements[current_placement_int])
+                        {
+                            message = Some(e.color(Color::Red).to_string());
+                        }
+                    }
+                    Ok(Key::Char('t')) => {
+                        let prompt = format!(
+                            "Place {} at (like B7 h or B7 v): ",
+                            current_ship_type.to_string()
+                        );
+                        if remaining == 0 {
+                            message = Some(
+                                PlacementError::FleetQuotaExceeded
+                                    .to_string()
+                                    .color(Color::Red)
+                                    .to_string(),
+                            );
+                        } else if let Some(text) = Self::read_text(&g, &prompt) {
+                            let placement = self
+                                .state
+                                .board(self.state.turn())
+                                .parse_placement(&text)
+                                .map_err(|e| e.to_string())
+                                .and_then(|(x, y, orientation)| {
+                                    self.state
+                                        .place_ship(current_ship_type, x, y, orientation)
+                                        .map_err(|e| e.to_string())
+                                });
+                            if let Err(e) = placement {
+                                message = Some(e.color(Color::Red).to_string());
                             }
                         }
                     }
+                    Ok(Key::Char('m')) => {
+                        if self.state.board(self.state.turn()).get_ships().is_empty() {
+                            message = Some("No ships have been placed yet".to_string());
+                        } else {
+                            x = 0;
+                            y = 0;
+                            pick_mode = true;
+                        }
+                    }
+                    Ok(Key::Char('c')) => {
+                        if let Err(e) = self.state.clear_ships() {
+                            message = Some(e.color(Color::Red).to_string());
+                        }
+                    }
+                    Ok(Key::Char('p')) => {
+                        current_placement_int = (current_placement_int + 1) % placements.len();
+                    }
+                    Ok(Key::Esc) => match self.state.finish_setup() {
+                        Ok(()) => fininished = true,
+                        Err(e) => message = Some(e.color(Color::Red).to_string()),
+                    },
                     Ok(_) => (),
                     Err(e) => println!("{}", e),
                 }
             } else {
                 match c {
-                    Ok(Key::Char('r')) => {
-                        self.player_board
-                            .set_ship_types(x, y, &previous_ship_types, horizontal);
-                        horizontal = !horizontal;
-                        if horizontal
-                            && x >= (self.player_board.get_board_size()
-                                - current_ship_type.get_size())
-                        {
-                            x = self.player_board.get_board_size() - current_ship_type.get_size();
-                        } else if !horizontal
-                            && y >= self.player_board.get_board_size()
-                                - current_ship_type.get_size()
-                        {
-                            y = self.player_board.get_board_size() - current_ship_type.get_size();
+                    Ok(Key::Char('r')) => match orientation {
+                        Orientation::Horizontal if ship_size <= height => {
+                            orientation = Orientation::Vertical;
+                            y = y.min(height - ship_size);
                         }
-                        previous_ship_types = self.player_board.get_ship_types(
-                            x,
-                            y,
-                            current_ship_type.get_size(),
-                            horizontal,
-                        );
-                        self.player_board.set_ship_type(
-                            x,
-                            y,
-                            current_ship_type,
-                            current_ship_type.get_size(),
-                            horizontal,
-                        );
-                    }
+                        Orientation::Vertical if ship_size <= width => {
+                            orientation = Orientation::Horizontal;
+                            x = x.min(width - ship_size);
+                        }
+                        _ => {}
+                    },
                     Ok(Key::Esc) => {
+                        // A lifted ship goes back where it came from
+                        if let Some(ship) = lifted.take() {
+                            let _ = self.state.place_ship(
+                                &ship.ship_type,
+                                ship.x,
+                                ship.y,
+                                ship.orientation,
+                            );
+                        }
                         select_mode = true;
-                        self.player_board
-                            .set_ship_types(x, y, &previous_ship_types, horizontal);
                     }
                     Ok(Key::Char('\r')) => {
-                        if !self.player_board.any_overlap() {
-                            remaining_ships[current_ship_type_int] -= 1;
-                            self.player_board.set_ship_type(
-                                x,
-                                y,
-                                current_ship_type,
-                                current_ship_type.get_size(),
-                                horizontal,
-                            );
+                        if self
+                            .state
+                            .place_ship(current_ship_type, x, y, orientation)
+                            .is_ok()
+                        {
                             x = 0;
                             y = 0;
+                            lifted = None;
                             select_mode = true;
                         }
                     }
                     Ok(Key::Left) => {
-                        if x > 0 {
-                            self.player_board.set_ship_types(
-                                x,
-                                y,
-                                &previous_ship_types,
-                                horizontal,
-                            );
-                            x -= 1;
-                            previous_ship_types = self.player_board.get_ship_types(
-                                x,
-                                y,
-                                current_ship_type.get_size(),
-                                horizontal,
-                            );
-                            self.player_board.set_ship_type(
-                                x,
-                                y,
-                                current_ship_type,
-                                current_ship_type.get_size(),
-                                horizontal,
-                            );
-                        }
+                        x = x.saturating_sub(1);
                     }
                     Ok(Key::Right) => {
-                        let val = if horizontal {
-                            current_ship_type.get_size()
-                        } else {
-                            1
+                        let val = match orientation {
+                            Orientation::Horizontal => ship_size,
+                            Orientation::Vertical => 1,
                         };
-                        if x < self.player_board.get_board_size() - val {
-                            self.player_board.set_ship_types(
-                                x,
-                                y,
-                                &previous_ship_types,
-                                horizontal,
-                            );
+                        if x < width - val {
                             x += 1;
-                            previous_ship_types = self.player_board.get_ship_types(
-                                x,
-                                y,
-                                current_ship_type.get_size(),
-                                horizontal,
-                            );
-                            self.player_board.set_ship_type(
-                                x,
-                                y,
-                                current_ship_type,
-                                current_ship_type.get_size(),
-                                horizontal,
-                            );
                         }
                     }
                     Ok(Key::Up) => {
-                        if y > 0 {
-                            self.player_board.set_ship_types(
-                                x,
-                                y,
-                                &previous_ship_types,
-                                horizontal,
-                            );
-                            y -= 1;
-                            previous_ship_types = self.player_board.get_ship_types(
-                                x,
-                                y,
-                                current_ship_type.get_size(),
-                                horizontal,
-                            );
-                            self.player_board.set_ship_type(
-                                x,
-                                y,
-                                current_ship_type,
-                                current_ship_type.get_size(),
-                                horizontal,
-                            );
-                        }
+                        y = y.saturating_sub(1);
                     }
                     Ok(Key::Down) => {
-                        let val = if horizontal {
-                            1
-                        } else {
-                            current_ship_type.get_size()
+                        let val = match orientation {
+                            Orientation::Horizontal => 1,
+                            Orientation::Vertical => ship_size,
                         };
-                        if y < self.player_board.get_board_size() - val {
-                            self.player_board.set_ship_types(
-                                x,
-                                y,
-                                &previous_ship_types,
-                                horizontal,
-                            );
+                        if y < height - val {
                             y += 1;
-                            previous_ship_types = self.player_board.get_ship_types(
-                                x,
-                                y,
-                                current_ship_type.get_size(),
-                                horizontal,
-                            );
-                            self.player_board.set_ship_type(
-                                x,
-                                y,
-                                current_ship_type,
-                                current_ship_type.get_size(),
-                                horizontal,
-                            );
                         }
                     }
                     Ok(_) => (),
@@ -334,90 +679,286 @@ impl Game {
             }
             print!("{}[2J", 27 as char);
         }
-
-        self.play_game();
     }
 
     fn play_game(&mut self) {
-        let mut x: u32 = 0;
-        let mut y: u32 = 0;
+        let mut cursor = (0, 0);
+        let mut messages: Vec<String> = Vec::new();
+
+        let two_player = self.state.opponent() == Opponent::Human;
+        let mut at_keyboard = self.state.turn();
+        if two_player {
+            self.pass_keyboard(&[]);
+        }
 
-        let mut previous_ship_type = self.guess_board.get_ship(x, y).unwrap().ship_type;
+        while self.state.winner().is_none() {
+            if !self.state.is_human(self.state.turn()) {
+                match self.state.ai_turn() {
+                    Ok(shots) => {
+                        for shot in shots {
+                            messages.push(self.describe(Player::Ai, shot));
+                        }
+                        if self.state.turn() == Player::Ai && self.state.winner().is_none() {
+                            messages.push(format!(
+                                "The AI fires again ({} in a row)",
+                                self.state.hit_streak()
+                            ));
+                        }
+                    }
+                    Err(e) => {
+                        for message in &messages {
+                            println!("{}", message);
+                        }
+                        println!("{}", e.color(Color::Red));
+                        return;
+                    }
+                }
+                continue;
+            }
+
+            // Nobody may see the next player's fleet until they have the keyboard
+            if two_player && self.state.turn() != at_keyboard {
+                at_keyboard = self.state.turn();
+                self.pass_keyboard(&messages);
+            }
+            let shooter = self.state.turn();
+
+            let Some(targets) = self.choose_targets(&mut cursor, &messages) else {
+                return;
+            };
+            let shots = match self.state.rules().firing {
+                FiringMode::Single => {
+                    let (x, y) = targets[0];
+                    self.state
+                        .fire(x, y)
+                        .map(|result| vec![Shot { x, y, result }])
+                }
+                FiringMode::Salvo => self.state.fire_salvo(&targets),
+            };
+            messages = match shots {
+                Ok(shots) => shots
+                    .into_iter()
+                    .map(|shot| self.describe(shooter, shot))
+                    .collect(),
+                Err(e) => vec![e.color(Color::Red).to_string()],
+            };
+        }
+    }
 
-        self.guess_board
-            .update_ship_selection(&mut previous_ship_type, x, y);
+    /// Shows `player`'s view of both boards, followed by `messages`.
+    pub fn show_boards(&self, player: Player, selection: &Selection, messages: &[String]) {
+        println!("Other Player's Board");
+        self.state.board(player.opponent()).show(false, selection);
+        println!("Your Board");
+        self.state.board(player).show(true, &Selection::None);
 
-        let mut player_won = false;
+        println!();
+
+        for message in messages {
+            println!("{}", message);
+        }
+    }
+
+    /// Lets the player whose turn it is aim it: one target, or every shot of
+    /// the salvo in salvo mode. Network games can be resigned instead, which
+    /// gives nothing.
+    pub fn choose_targets(
+        &self,
+        cursor: &mut (u32, u32),
+        messages: &[String],
+    ) -> Option<Vec<(u32, u32)>> {
+        let shooter = self.state.turn();
+        let target_board = self.state.board(shooter.opponent());
+        let width = target_board.get_width();
+        let height = target_board.get_height();
+
+        let salvo = self.state.rules().firing == FiringMode::Salvo;
+        let salvo_size = self.state.salvo_size(shooter);
+        let mut marked: Vec<(u32, u32)> = Vec::new();
+        let mut notice: Option<String> = None;
+
+        let two_player = self.state.opponent() == Opponent::Human;
+        let remote = self.state.opponent() == Opponent::Remote;
 
-        let mut finished = false;
         let g = Getch::new();
 
-        while !finished {
-            println!("Other Player's Board");
-            self.guess_board.show();
-            println!("Your Board");
-            self.player_board.show();
+        loop {
+            let (x, y) = *cursor;
+            let selection = match salvo {
+                true => Selection::Salvo {
+                    x,
+                    y,
+                    marked: marked.clone(),
+                },
+                false => Selection::Cursor { x, y },
+            };
+            self.show_boards(shooter, &selection, messages);
+            if let Some(notice) = notice.take() {
+                println!("{}", notice);
+            }
 
+            let turn = match two_player {
+                true => format!("{}'s turn", self.player_name(shooter)),
+                false => "Your turn".to_string(),
+            };
+            match self.state.hit_streak() {
+                0 => println!("{}", turn.color(Color::Blue)),
+                streak => println!(
+                    "{}",
+                    format!("{}: {} hits in a row, fire again", turn, streak).color(Color::Blue)
+                ),
+            }
             println!();
 
             println!("Change/Move Selection: Arrow Keys");
-            println!("Confirm Selection: Enter Key");
+            if salvo {
+                println!("Salvo: {} of {} shots marked", marked.len(), salvo_size);
+                println!("Mark/Unmark Target: Enter Key");
+                println!("Type A Position To Mark: T Key");
+                println!("Fire Salvo: F Key");
+            } else {
+                println!("Confirm Selection: Enter Key");
+                println!("Type A Position To Fire At: T Key");
+            }
+            match remote {
+                true => println!("Resign: Q Key"),
+                false => println!("Save Game: S Key"),
+            }
 
+            let mut chosen = None;
             match g.getch() {
                 Ok(Key::Left) => {
-                    if x > 0 {
-                        self.guess_board.get_ship(x, y).unwrap().ship_type = previous_ship_type;
-                        x -= 1;
-                        self.guess_board
-                            .update_ship_selection(&mut previous_ship_type, x, y);
-                    }
+                    cursor.0 = x.saturating_sub(1);
                 }
                 Ok(Key::Right) => {
-                    if x < self.guess_board.get_board_size() - 1 {
-                        self.guess_board.get_ship(x, y).unwrap().ship_type = previous_ship_type;
-                        x += 1;
-                        self.guess_board
-                            .update_ship_selection(&mut previous_ship_type, x, y);
+                    if x < width - 1 {
+                        cursor.0 += 1;
                     }
                 }
                 Ok(Key::Up) => {
-                    if y > 0 {
-                        self.guess_board.get_ship(x, y).unwrap().ship_type = previous_ship_type;
-                        y -= 1;
-                        self.guess_board
-                            .update_ship_selection(&mut previous_ship_type, x, y);
-                    }
+                    cursor.1 = y.saturating_sub(1);
                 }
                 Ok(Key::Down) => {
-                    if y < self.guess_board.get_board_size() - 1 {
-                        self.guess_board.get_ship(x, y).unwrap().ship_type = previous_ship_type;
-                        y += 1;
-                        self.guess_board
-                            .update_ship_selection(&mut previous_ship_type, x, y);
+                    if y < height - 1 {
+                        cursor.1 += 1;
                     }
                 }
-                Ok(Key::Char('\r')) => {
-                    if previous_ship_type != ShipType::Shot {
-                        self.ai_board.hit_ship(x, y);
-                        let ship = self.guess_board.get_ship(x, y).unwrap();
-                        ship.damaged = self.ai_board.get_ship(x, y).unwrap().damaged;
-                        ship.ship_type = ShipType::Shot;
-                        self.guess_board
-                            .update_ship_selection(&mut previous_ship_type, x, y);
-                        self.ai
-                            .do_turn(&mut self.player_board, self.game_difficulty);
-                        if self.ai_board.all_destroyed() || self.player_board.all_destroyed() {
-                            finished = true;
-                            player_won = self.ai_board.all_destroyed();
+                Ok(Key::Char('s')) if !remote => notice = Some(self.save_game()),
+                Ok(Key::Char('q')) if remote => {
+                    print!("{}[2J", 27 as char);
+                    return None;
+                }
+                Ok(Key::Char('\r')) => chosen = Some(*cursor),
+                Ok(Key::Char('t')) => {
+                    let prompt = if salvo { "Mark: " } else { "Fire at: " };
+                    if let Some(text) = Self::read_text(&g, prompt) {
+                        match target_board.parse_position(&text) {
+                            Ok(position) => {
+                                *cursor = position;
+                                chosen = Some(position);
+                            }
+                            Err(e) => notice = Some(e.to_string().color(Color::Red).to_string()),
                         }
                     }
                 }
+                Ok(Key::Char('f')) if salvo => {
+                    if marked.len() as u32 == salvo_size {
+                        print!("{}[2J", 27 as char);
+                        return Some(marked);
+                    }
+                    notice = Some(
+                        format!("Mark {} shots to fire", salvo_size)
+                            .color(Color::Red)
+                            .to_string(),
+                    );
+                }
                 Ok(_) => {}
                 Err(e) => println!("{}", e),
             }
 
+            if let Some(position) = chosen {
+                let fired = target_board
+                    .get_cell(position.0, position.1)
+                    .map_or(true, |cell| cell.is_fired());
+                if let Some(index) = marked.iter().position(|target| *target == position) {
+                    marked.remove(index);
+                } else if fired {
+                    notice = Some("You have already fired there".to_string());
+                } else if !salvo {
+                    print!("{}[2J", 27 as char);
+                    return Some(vec![position]);
+                } else if marked.len() as u32 >= salvo_size {
+                    notice = Some(format!("Your salvo only has {} shots", salvo_size));
+                } else {
+                    marked.push(position);
+                }
+            }
             print!("{}[2J", 27 as char);
         }
+    }
+
+    /// Reads a line typed after `prompt`. Escape gives up and returns nothing.
+    pub fn read_text(g: &Getch, prompt: &str) -> Option<String> {
+        print!("{}", prompt);
+        let _ = io::stdout().flush();
+
+        let mut text = String::new();
+        loop {
+            match g.getch() {
+                Ok(Key::Char('\r')) => {
+                    println!();
+                    return Some(text);
+                }
+                Ok(Key::Esc) => return None,
+                Ok(Key::Backspace) | Ok(Key::Delete) | Ok(Key::Char('\x7f')) => {
+                    if text.pop().is_some() {
+                        print!("\x08 \x08");
+                    }
+                }
+                Ok(Key::Char(c)) if !c.is_control() => {
+                    text.push(c);
+                    print!("{}", c);
+                }
+                Ok(_) => {}
+                Err(e) => println!("{}", e),
+            }
+            let _ = io::stdout().flush();
+        }
+    }
+
+    fn describe_shot(shooter: &str, owner: &str, shot: Shot) -> String {
+        let position = Board::position_label(shot.x, shot.y);
+        match shot.result {
+            FireResult::Miss => format!("{} missed at {}", shooter, position),
+            FireResult::Hit => format!("{} hit at {}", shooter, position)
+                .color(Color::Yellow)
+                .to_string(),
+            FireResult::Sunk(ship_type) => {
+                format!("{} sank {} {}", shooter, owner, ship_type.to_string())
+                    .color(Color::Red)
+                    .to_string()
+            }
+            FireResult::AlreadyFired | FireResult::OutOfBounds => String::new(),
+        }
+    }
+
+    fn show_result(&self) {
+        if self.state.opponent() == Opponent::Human {
+            if let Some(winner) = self.state.winner() {
+                println!(
+                    "{}",
+                    format!("{} Won!", self.player_name(winner)).color(Color::Green)
+                );
+            }
+            for player in [Player::Human, Player::Ai] {
+                println!("{} Board", self.state.opponent().possessive(player));
+                self.state.board(player).show(true, &Selection::None);
+            }
+            self.save_replay();
+            return;
+        }
+
+        let player_won = self.state.winner() == Some(Player::Human);
 
         let mut color: Color = Color::Green;
         if !player_won {
@@ -428,8 +969,30 @@ impl Game {
             if player_won { "You Won!" } else { "AI Won!" }.color(color)
         );
         println!("Your Opponent's Board");
-        self.ai_board.show();
+        self.state.ai_board().show(true, &Selection::None);
         println!("Your Board");
-        self.player_board.show();
+        self.state.player_board().show(true, &Selection::None);
+        println!(
+            "Seed: {} (play the same AI again with --seed {})",
+            self.state.seed(),
+            self.state.seed()
+        );
+        self.save_replay();
+    }
+
+    fn save_replay(&self) {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let log_path = PathBuf::from(format!("battleships-replay-{}.json", seconds));
+        match self.state.log().save(&log_path) {
+            Ok(()) => println!(
+                "Replay saved to {}, watch it with --replay {}",
+                log_path.display(),
+                log_path.display()
+            ),
+            Err(e) => println!("{}", e.to_string().color(Color::Red)),
+        }
     }
 }
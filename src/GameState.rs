@@ -0,0 +1,1023 @@
+use crate::{
+    Board::{Board, FireResult, PlacementError, Shot},
+    Cell::ShotResult,
+    Difficulty::Difficulty,
+    Fleet::FleetConfig,
+    MoveLog::{Move, MoveLog},
+    Rules::{FiringMode, Rules},
+    Ship::{Orientation, PlacedShip, ShipType},
+    Targeting::StrategyRegistry,
+    AI::AI,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+/// The random number generator behind every random choice in a game. It is
+/// the same algorithm as `StdRng`, but can be written to a save file.
+pub type GameRng = ChaCha12Rng;
+
+/// The stream of the game's seed the AI lays out its fleet with, kept apart
+/// from the one that places the player's ships.
+const AI_FLEET_STREAM: u64 = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Player {
+    #[default]
+    Human,
+    Ai,
+}
+
+impl Player {
+    pub fn opponent(&self) -> Self {
+        match self {
+            Self::Human => Self::Ai,
+            Self::Ai => Self::Human,
+        }
+    }
+}
+
+/// Who plays the second side. In a two-player game `Player::Ai` is the
+/// second human at the keyboard, and in a network game the player at the
+/// other end, whose fleet this game never sees.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Opponent {
+    #[default]
+    Ai,
+    Human,
+    Remote,
+}
+
+impl Opponent {
+    /// The opponents that can be played on this computer alone.
+    pub fn all() -> [Self; 2] {
+        [Self::Ai, Self::Human]
+    }
+
+    pub fn to_string(&self) -> &'static str {
+        match self {
+            Self::Ai => "Play against the AI",
+            Self::Human => "Two players on this computer",
+            Self::Remote => "Play over the network",
+        }
+    }
+
+    /// What `player` is called on screen in a game against this opponent.
+    pub fn player_name(&self, player: Player) -> &'static str {
+        match (self, player) {
+            (Self::Ai, Player::Human) => "You",
+            (Self::Ai, Player::Ai) => "The AI",
+            (Self::Human, Player::Human) => "Player 1",
+            (Self::Human, Player::Ai) => "Player 2",
+            (Self::Remote, Player::Human) => "You",
+            (Self::Remote, Player::Ai) => "Your opponent",
+        }
+    }
+
+    /// Whose something of `player`'s is, such as "Your" or "Player 2's".
+    pub fn possessive(&self, player: Player) -> &'static str {
+        match (self, player) {
+            (Self::Ai, Player::Human) => "Your",
+            (Self::Ai, Player::Ai) => "The AI's",
+            (Self::Human, Player::Human) => "Player 1's",
+            (Self::Human, Player::Ai) => "Player 2's",
+            (Self::Remote, Player::Human) => "Your",
+            (Self::Remote, Player::Ai) => "Your opponent's",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Phase {
+    Setup,
+    Playing,
+    Finished,
+}
+
+/// The rules of a single game, with no terminal input or output.
+#[derive(Serialize, Deserialize)]
+pub struct GameState {
+    player_board: Board,
+    ai_board: Board,
+    ai: AI,
+    #[serde(default)]
+    opponent: Opponent,
+    difficulty: Difficulty,
+    rules: Rules,
+    phase: Phase,
+    turn: Player,
+    /// Who fires first once setup is finished.
+    #[serde(default)]
+    first: Player,
+    turn_number: u32,
+    /// Extra turns in a row the current player has earned by hitting.
+    #[serde(default)]
+    hit_streak: u32,
+    /// Ships a remote player has reported sunk, since their fleet is never on
+    /// this computer.
+    #[serde(default)]
+    remote_sunk: u32,
+    log: MoveLog,
+    seed: u64,
+    rng: GameRng,
+    #[serde(skip)]
+    registry: StrategyRegistry,
+}
+
+impl GameState {
+    pub fn new() -> Self {
+        Self::with_rules(Rules::new()).unwrap()
+    }
+
+    pub fn with_rules(rules: Rules) -> Result<Self, &'static str> {
+        Self::with_seed(rules, rand::random())
+    }
+
+    /// A game whose AI fleet and shots are the same every time for the same seed.
+    pub fn with_seed(rules: Rules, seed: u64) -> Result<Self, &'static str> {
+        let registry = StrategyRegistry::new();
+        let ai = AI::new(
+            registry.strategy_for(Difficulty::Easy),
+            registry.placement_for(Difficulty::Easy),
+        );
+        Self::build(rules, seed, ai, Opponent::Ai, registry)
+    }
+
+    fn build(
+        rules: Rules,
+        seed: u64,
+        ai: AI,
+        opponent: Opponent,
+        registry: StrategyRegistry,
+    ) -> Result<Self, &'static str> {
+        rules.validate()?;
+
+        Ok(Self {
+            player_board: Board::new(&rules),
+            ai_board: Board::new(&rules),
+            ai,
+            opponent,
+            difficulty: Difficulty::Easy,
+            log: MoveLog::new(rules.clone(), opponent),
+            rules,
+            phase: Phase::Setup,
+            turn: Player::Human,
+            first: Player::Human,
+            turn_number: 1,
+            hit_streak: 0,
+            remote_sunk: 0,
+            seed,
+            rng: GameRng::seed_from_u64(seed),
+            registry,
+        })
+    }
+
+    /// Empties the second side's board, keeping the player's placements in
+    /// the log. The AI's fleet is only laid out once setup is finished.
+    fn clear_ai_fleet(&mut self) {
+        self.ai_board = Board::new(&self.rules);
+        self.log
+            .retain(|game_move| game_move.get_player() == Player::Human);
+    }
+
+    /// Lays out the AI's fleet. It has its own generator seeded from the game,
+    /// so the same seed gives the same fleet however the player placed theirs,
+    /// and a stream of its own so that fleet is not the player's.
+    fn place_ai_fleet(&mut self) -> Result<(), &'static str> {
+        let mut ai_board = Board::new(&self.rules);
+        let mut rng = GameRng::seed_from_u64(self.seed);
+        rng.set_stream(AI_FLEET_STREAM);
+        self.ai
+            .place_ships(&mut ai_board, &self.registry, &mut rng)?;
+
+        for ship in ai_board.get_ships() {
+            self.log.push(Move::Place {
+                player: Player::Ai,
+                ship: ship.clone(),
+            });
+        }
+        self.ai_board = ai_board;
+        Ok(())
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn fleet(&self) -> &FleetConfig {
+        &self.rules.fleet
+    }
+
+    pub fn player_board(&self) -> &Board {
+        &self.player_board
+    }
+
+    pub fn ai_board(&self) -> &Board {
+        &self.ai_board
+    }
+
+    /// The board holding `player`'s own fleet.
+    pub fn board(&self, player: Player) -> &Board {
+        match player {
+            Player::Human => &self.player_board,
+            Player::Ai => &self.ai_board,
+        }
+    }
+
+    fn board_mut(&mut self, player: Player) -> &mut Board {
+        match player {
+            Player::Human => &mut self.player_board,
+            Player::Ai => &mut self.ai_board,
+        }
+    }
+
+    pub fn opponent(&self) -> Opponent {
+        self.opponent
+    }
+
+    /// Whether `player` is someone at the keyboard rather than the AI.
+    pub fn is_human(&self, player: Player) -> bool {
+        player == Player::Human || self.opponent == Opponent::Human
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn turn(&self) -> Player {
+        self.turn
+    }
+
+    pub fn turn_number(&self) -> u32 {
+        self.turn_number
+    }
+
+    pub fn hit_streak(&self) -> u32 {
+        self.hit_streak
+    }
+
+    pub fn log(&self) -> &MoveLog {
+        &self.log
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn registry(&self) -> &StrategyRegistry {
+        &self.registry
+    }
+
+    /// Lets strategies be added or assigned to difficulties before the game starts.
+    pub fn registry_mut(&mut self) -> &mut StrategyRegistry {
+        &mut self.registry
+    }
+
+    pub fn strategy(&self) -> &str {
+        self.ai.get_strategy_name()
+    }
+
+    pub fn placement(&self) -> &str {
+        self.ai.get_placement_name()
+    }
+
+    /// Goes back to the strategies of the difficulty for any the registry
+    /// does not know, such as custom ones after loading a save.
+    pub(crate) fn fall_back_to_difficulty(&mut self) {
+        let strategy = match self.registry.contains(self.ai.get_strategy_name()) {
+            true => self.ai.get_strategy_name(),
+            false => self.registry.strategy_for(self.difficulty),
+        };
+        let placement = match self
+            .registry
+            .contains_placement(self.ai.get_placement_name())
+        {
+            true => self.ai.get_placement_name(),
+            false => self.registry.placement_for(self.difficulty),
+        };
+        self.ai = AI::new(strategy, placement);
+    }
+
+    /// Checks that the rules are valid and that both boards and the log were
+    /// made for them, which a game read from a file may not be.
+    pub(crate) fn check_rules(&self) -> Result<(), &'static str> {
+        self.rules.validate()?;
+        if *self.log.get_rules() != self.rules {
+            return Err("The move log does not match the rules");
+        }
+        for board in [&self.player_board, &self.ai_board] {
+            if board.get_width() != self.rules.width || board.get_height() != self.rules.height {
+                return Err("The boards do not match the size in the rules");
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the game can be written to a save file, or why not.
+    pub fn can_save(&self) -> Result<(), &'static str> {
+        if self.opponent == Opponent::Remote {
+            return Err("Network games cannot be saved");
+        }
+        if !self.registry.can_save(self.strategy()) || !self.registry.can_save(self.placement()) {
+            return Err("Games against an AI that runs outside the game cannot be saved");
+        }
+        Ok(())
+    }
+
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) -> Result<(), &'static str> {
+        if self.phase != Phase::Setup {
+            return Err("The difficulty can only be changed during setup");
+        }
+        self.difficulty = difficulty;
+        self.ai = AI::new(
+            self.registry.strategy_for(difficulty),
+            self.registry.placement_for(difficulty),
+        );
+        Ok(())
+    }
+
+    /// Switches between playing the AI and two players taking turns.
+    pub fn set_opponent(&mut self, opponent: Opponent) -> Result<(), &'static str> {
+        if self.phase != Phase::Setup {
+            return Err("The opponent can only be changed during setup");
+        }
+        self.opponent = opponent;
+        self.log.set_opponent(opponent);
+        self.turn = Player::Human;
+        self.clear_ai_fleet();
+        Ok(())
+    }
+
+    /// Lets `player` fire first once setup is finished.
+    pub fn set_first_player(&mut self, player: Player) -> Result<(), &'static str> {
+        if self.phase != Phase::Setup {
+            return Err("The first player can only be changed during setup");
+        }
+        self.first = player;
+        Ok(())
+    }
+
+    /// Makes the AI use a registered strategy instead of the one for its difficulty.
+    pub fn set_strategy(&mut self, name: &str) -> Result<(), &'static str> {
+        if self.phase != Phase::Setup {
+            return Err("The strategy can only be changed during setup");
+        }
+        if !self.registry.contains(name) {
+            return Err("No strategy is registered under this name");
+        }
+        self.ai = AI::new(name, self.ai.get_placement_name());
+        Ok(())
+    }
+
+    /// Makes the AI lay out its fleet with a registered placement strategy.
+    pub fn set_placement(&mut self, name: &str) -> Result<(), &'static str> {
+        if self.phase != Phase::Setup {
+            return Err("The placement can only be changed during setup");
+        }
+        if !self.registry.contains_placement(name) {
+            return Err("No placement strategy is registered under this name");
+        }
+        self.ai = AI::new(self.ai.get_strategy_name(), name);
+        Ok(())
+    }
+
+    /// Replaces the rules for both sides, clearing any ships placed so far.
+    pub fn set_rules(&mut self, rules: Rules) -> Result<(), &'static str> {
+        if self.phase != Phase::Setup {
+            return Err("The rules can only be changed during setup");
+        }
+        let ai = AI::new(self.ai.get_strategy_name(), self.ai.get_placement_name());
+        let mut state = Self::build(
+            rules,
+            self.seed,
+            ai,
+            self.opponent,
+            std::mem::take(&mut self.registry),
+        )?;
+        state.difficulty = self.difficulty;
+        state.first = self.first;
+        *self = state;
+        Ok(())
+    }
+
+    /// How many more ships of a type the player placing their fleet may place.
+    pub fn remaining_ships(&self, ship_type: &ShipType) -> u32 {
+        self.board(self.turn).remaining_ships(ship_type)
+    }
+
+    pub fn all_ships_placed(&self) -> bool {
+        self.board(self.turn).all_ships_placed()
+    }
+
+    pub fn can_place_ship(
+        &self,
+        ship_type: &ShipType,
+        x: u32,
+        y: u32,
+        orientation: Orientation,
+    ) -> Result<(), PlacementError> {
+        if self.phase != Phase::Setup {
+            return Err(PlacementError::SetupFinished);
+        }
+        self.board(self.turn)
+            .can_place(ship_type, x, y, orientation)
+    }
+
+    pub fn place_ship(
+        &mut self,
+        ship_type: &ShipType,
+        x: u32,
+        y: u32,
+        orientation: Orientation,
+    ) -> Result<(), PlacementError> {
+        self.can_place_ship(ship_type, x, y, orientation)?;
+        let player = self.turn;
+        let ship_id = self.board_mut(player).place(ship_type, x, y, orientation)?;
+        self.log.push(Move::Place {
+            player,
+            ship: self.board(player).get_ship(ship_id).unwrap().clone(),
+        });
+        Ok(())
+    }
+
+    /// Places the remaining ships of the player placing their fleet with a registered placement strategy.
+    pub fn auto_place(&mut self, placement: &str) -> Result<(), &'static str> {
+        if self.phase != Phase::Setup {
+            return Err("Ships can only be placed during setup");
+        }
+        let mut strategy = self
+            .registry
+            .create_placement(placement)
+            .ok_or("No placement strategy is registered under this name")?;
+
+        let player = self.turn;
+        let board = match player {
+            Player::Human => &mut self.player_board,
+            Player::Ai => &mut self.ai_board,
+        };
+        let placed = board.get_ships().len();
+        strategy.place_fleet(board, &mut self.rng)?;
+        for ship in &board.get_ships()[placed..] {
+            self.log.push(Move::Place {
+                player,
+                ship: ship.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Takes one of the player's ships off the board so it can be placed again.
+    pub fn lift_ship(&mut self, x: u32, y: u32) -> Result<PlacedShip, &'static str> {
+        if self.phase != Phase::Setup {
+            return Err("Ships can only be moved during setup");
+        }
+        let player = self.turn;
+        let ship = self
+            .board_mut(player)
+            .remove_ship_at(x, y)
+            .ok_or("There is no ship there")?;
+
+        let mut removed = false;
+        self.log.retain(|game_move| match game_move {
+            Move::Place {
+                player: placer,
+                ship: placed,
+            } if !removed && *placer == player && *placed == ship => {
+                removed = true;
+                false
+            }
+            _ => true,
+        });
+        Ok(ship)
+    }
+
+    /// Removes every ship the player has placed.
+    pub fn clear_ships(&mut self) -> Result<(), &'static str> {
+        if self.phase != Phase::Setup {
+            return Err("Ships can only be cleared during setup");
+        }
+        let player = self.turn;
+        self.board_mut(player).clear();
+        self.log
+            .retain(|game_move| game_move.get_player() != player);
+        Ok(())
+    }
+
+    /// Replaces the player's whole fleet with a new automatic layout.
+    pub fn reroll_ships(&mut self, placement: &str) -> Result<(), &'static str> {
+        if !self.registry.contains_placement(placement) {
+            return Err("No placement strategy is registered under this name");
+        }
+        self.clear_ships()?;
+        self.auto_place(placement)
+    }
+
+    /// Ends the current player's setup. In a two-player game the first call
+    /// hands the setup over to the second player.
+    pub fn finish_setup(&mut self) -> Result<(), &'static str> {
+        if self.phase != Phase::Setup {
+            return Err("Setup has already finished");
+        }
+        if !self.all_ships_placed() {
+            return Err("Not all ships have been placed");
+        }
+        if self.opponent == Opponent::Human && self.turn == Player::Human {
+            self.turn = Player::Ai;
+            return Ok(());
+        }
+        if self.opponent == Opponent::Ai {
+            self.place_ai_fleet()?;
+        }
+        self.phase = Phase::Playing;
+        self.turn = self.first;
+        Ok(())
+    }
+
+    /// Ships `player` still has afloat.
+    pub fn ships_afloat(&self, player: Player) -> u32 {
+        match (self.opponent, player) {
+            (Opponent::Remote, Player::Ai) => self
+                .rules
+                .fleet
+                .total_ships()
+                .saturating_sub(self.remote_sunk),
+            _ => self.board(player).ships_afloat(),
+        }
+    }
+
+    /// How many shots a player fires each turn.
+    pub fn salvo_size(&self, player: Player) -> u32 {
+        let target_board = self.board(player.opponent());
+        self.rules
+            .firing
+            .shots(self.ships_afloat(player), target_board.unfired_cells())
+    }
+
+    /// Checks that `targets` are a whole turn of shots for `shooter`, each at
+    /// a different cell that has not been fired at.
+    fn check_targets(&self, shooter: Player, targets: &[(u32, u32)]) -> Result<(), &'static str> {
+        if targets.len() as u32 != self.salvo_size(shooter) {
+            return Err("The salvo has the wrong number of shots");
+        }
+        for (index, (x, y)) in targets.iter().enumerate() {
+            if self.board(shooter.opponent()).get_cell(*x, *y)?.is_fired() {
+                return Err("The salvo fires at a cell that was already fired at");
+            }
+            if targets[..index].contains(&(*x, *y)) {
+                return Err("The salvo fires at the same cell twice");
+            }
+        }
+        Ok(())
+    }
+
+    /// Fires for whichever player at the keyboard has the turn.
+    pub fn fire(&mut self, x: u32, y: u32) -> Result<FireResult, &'static str> {
+        if self.phase != Phase::Playing || !self.is_human(self.turn) {
+            return Err("It is not your turn");
+        }
+        if self.opponent == Opponent::Remote {
+            return Err("Shots at a remote player are answered over the network");
+        }
+        if self.rules.firing == FiringMode::Salvo {
+            return Err("Each turn fires a whole salvo");
+        }
+
+        let shooter = self.turn;
+        let result = self.board_mut(shooter.opponent()).fire_at(x, y);
+        if result.is_valid_shot() {
+            self.log_shot(shooter, x, y, &result);
+            self.end_turn(result.is_hit());
+        }
+        Ok(result)
+    }
+
+    /// Fires every shot of the player's turn at once. Nothing is fired unless
+    /// the whole salvo is valid.
+    pub fn fire_salvo(&mut self, targets: &[(u32, u32)]) -> Result<Vec<Shot>, &'static str> {
+        if self.phase != Phase::Playing || !self.is_human(self.turn) {
+            return Err("It is not your turn");
+        }
+        if self.opponent == Opponent::Remote {
+            return Err("Shots at a remote player are answered over the network");
+        }
+        let shooter = self.turn;
+        self.check_targets(shooter, targets)?;
+
+        let mut shots = Vec::new();
+        for (x, y) in targets {
+            let result = self.board_mut(shooter.opponent()).fire_at(*x, *y);
+            self.log_shot(shooter, *x, *y, &result);
+            shots.push(Shot {
+                x: *x,
+                y: *y,
+                result,
+            });
+        }
+        self.end_turn(shots.iter().any(|shot| shot.result.is_hit()));
+        Ok(shots)
+    }
+
+    /// Plays the AI's whole turn, returning every shot it fired.
+    pub fn ai_turn(&mut self) -> Result<Vec<Shot>, &'static str> {
+        if self.opponent != Opponent::Ai {
+            return Err("There is no AI in this game");
+        }
+        if self.phase != Phase::Playing || self.turn != Player::Ai {
+            return Err("It is not the AI's turn");
+        }
+
+        let salvo_size = self.salvo_size(Player::Ai);
+        let shots = match self.rules.firing {
+            FiringMode::Single => {
+                vec![self
+                    .ai
+                    .do_turn(&mut self.player_board, &self.registry, &mut self.rng)?]
+            }
+            FiringMode::Salvo => self.ai.do_salvo(
+                &mut self.player_board,
+                salvo_size,
+                &self.registry,
+                &mut self.rng,
+            )?,
+        };
+        for shot in &shots {
+            self.log_shot(Player::Ai, shot.x, shot.y, &shot.result);
+        }
+
+        self.end_turn(shots.iter().any(|shot| shot.result.is_hit()));
+        Ok(shots)
+    }
+
+    /// Records the player's turn against a remote player, with the results
+    /// they reported for each shot.
+    pub fn record_shots(&mut self, shots: &[Shot]) -> Result<(), &'static str> {
+        if self.opponent != Opponent::Remote {
+            return Err("Only shots at a remote player are recorded");
+        }
+        if self.phase != Phase::Playing || self.turn != Player::Human {
+            return Err("It is not your turn");
+        }
+        let targets: Vec<(u32, u32)> = shots.iter().map(|shot| (shot.x, shot.y)).collect();
+        self.check_targets(Player::Human, &targets)?;
+
+        for shot in shots {
+            let result = match shot.result {
+                FireResult::Miss => ShotResult::Miss,
+                FireResult::Hit | FireResult::Sunk(_) => ShotResult::Hit,
+                FireResult::AlreadyFired | FireResult::OutOfBounds => {
+                    return Err("A remote player can only report a miss, a hit or a sunk ship")
+                }
+            };
+            self.ai_board.record_shot(shot.x, shot.y, result)?;
+            if let FireResult::Sunk(_) = shot.result {
+                self.remote_sunk += 1;
+            }
+            self.log_shot(Player::Human, shot.x, shot.y, &shot.result);
+        }
+        self.end_turn(shots.iter().any(|shot| shot.result.is_hit()));
+        Ok(())
+    }
+
+    /// Fires a remote player's turn at the player's fleet. Nothing is fired
+    /// unless every shot is valid.
+    pub fn receive_shots(&mut self, targets: &[(u32, u32)]) -> Result<Vec<Shot>, &'static str> {
+        if self.opponent != Opponent::Remote {
+            return Err("There is no remote player in this game");
+        }
+        if self.phase != Phase::Playing || self.turn != Player::Ai {
+            return Err("It is not the remote player's turn");
+        }
+        self.check_targets(Player::Ai, targets)?;
+
+        let mut shots = Vec::new();
+        for (x, y) in targets {
+            let result = self.player_board.fire_at(*x, *y);
+            self.log_shot(Player::Ai, *x, *y, &result);
+            shots.push(Shot {
+                x: *x,
+                y: *y,
+                result,
+            });
+        }
+        self.end_turn(shots.iter().any(|shot| shot.result.is_hit()));
+        Ok(shots)
+    }
+
+    pub fn winner(&self) -> Option<Player> {
+        let remote_sunk =
+            self.opponent == Opponent::Remote && self.remote_sunk >= self.rules.fleet.total_ships();
+        if self.phase == Phase::Setup {
+            None
+        } else if self.ai_board.all_destroyed() || remote_sunk {
+            Some(Player::Human)
+        } else if self.player_board.all_destroyed() {
+            Some(Player::Ai)
+        } else {
+            None
+        }
+    }
+
+    fn log_shot(&mut self, player: Player, x: u32, y: u32, result: &FireResult) {
+        self.log.push(Move::Fire {
+            player,
+            turn: self.turn_number,
+            x,
+            y,
+            result: result.clone(),
+        });
+    }
+
+    /// Passes the turn to the other player, unless a hit lets the current
+    /// one fire again.
+    fn end_turn(&mut self, hit: bool) {
+        if self.winner().is_some() {
+            self.phase = Phase::Finished;
+            return;
+        }
+        if hit && self.rules.fire_again.allows(self.hit_streak) {
+            self.hit_streak += 1;
+        } else {
+            self.hit_streak = 0;
+            self.turn = self.turn.opponent();
+        }
+        self.turn_number += 1;
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rules::FireAgain;
+
+    fn ready(rules: Rules) -> GameState {
+        let mut state = GameState::with_seed(rules, 7).unwrap();
+        state.auto_place("uniform").unwrap();
+        state.finish_setup().unwrap();
+        state
+    }
+
+    /// Every cell of the AI's fleet.
+    fn ai_fleet_cells(state: &GameState) -> Vec<(u32, u32)> {
+        state
+            .ai_board()
+            .get_ships()
+            .iter()
+            .flat_map(|ship| ship.get_cells())
+            .collect()
+    }
+
+    fn first_water(board: &Board) -> (u32, u32) {
+        (0..board.get_height())
+            .flat_map(|y| (0..board.get_width()).map(move |x| (x, y)))
+            .find(|(x, y)| board.get_ship_at(*x, *y).is_none())
+            .unwrap()
+    }
+
+    #[test]
+    fn setup_needs_every_ship() {
+        let mut state = GameState::with_seed(Rules::new(), 7).unwrap();
+        assert_eq!(state.finish_setup(), Err("Not all ships have been placed"));
+        assert_eq!(state.fire(0, 0), Err("It is not your turn"));
+
+        state.auto_place("uniform").unwrap();
+        assert!(state.all_ships_placed());
+        state.finish_setup().unwrap();
+        assert_eq!(state.phase(), Phase::Playing);
+        assert_eq!(state.turn(), Player::Human);
+        assert_eq!(state.ai_board().get_ships().len(), 5);
+        assert_eq!(
+            state.place_ship(
+                &state.fleet().ships()[0].clone(),
+                0,
+                0,
+                Orientation::Horizontal
+            ),
+            Err(PlacementError::SetupFinished)
+        );
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_ai_fleet() {
+        let mut first = GameState::with_seed(Rules::new(), 7).unwrap();
+        first.auto_place("uniform").unwrap();
+        first.finish_setup().unwrap();
+        let mut second = GameState::with_seed(Rules::new(), 7).unwrap();
+        second.auto_place("clustered").unwrap();
+        second.finish_setup().unwrap();
+
+        assert_eq!(first.ai_board().get_ships(), second.ai_board().get_ships());
+    }
+
+    #[test]
+    fn the_ai_fleet_is_not_the_players() {
+        for seed in 0..20 {
+            let mut state = GameState::with_seed(Rules::new(), seed).unwrap();
+            state.auto_place("uniform").unwrap();
+            state.finish_setup().unwrap();
+            assert_ne!(
+                state.ai_board().get_ships(),
+                state.player_board().get_ships()
+            );
+        }
+    }
+
+    #[test]
+    fn a_miss_passes_the_turn() {
+        let mut state = ready(Rules::new());
+        let (x, y) = first_water(state.ai_board());
+
+        assert_eq!(state.fire(x, y), Ok(FireResult::Miss));
+        assert_eq!(state.turn(), Player::Ai);
+        assert_eq!(state.fire(x, y), Err("It is not your turn"));
+
+        assert_eq!(state.ai_turn().unwrap().len(), 1);
+        assert_eq!(state.turn(), Player::Human);
+        assert_eq!(state.fire(x, y), Ok(FireResult::AlreadyFired));
+        assert_eq!(state.turn(), Player::Human);
+    }
+
+    #[test]
+    fn sinking_every_ship_wins() {
+        let mut state = ready(Rules::new());
+        for (x, y) in ai_fleet_cells(&state) {
+            assert_eq!(state.winner(), None);
+            while state.turn() == Player::Ai {
+                state.ai_turn().unwrap();
+            }
+            assert!(state.fire(x, y).unwrap().is_hit());
+        }
+
+        assert_eq!(state.winner(), Some(Player::Human));
+        assert_eq!(state.phase(), Phase::Finished);
+        assert!(state.fire(0, 0).is_err());
+    }
+
+    #[test]
+    fn a_hit_fires_again_up_to_the_cap() {
+        let mut rules = Rules::new();
+        rules.fire_again = FireAgain::UpTo(1);
+        let mut state = ready(rules);
+        let cells = ai_fleet_cells(&state);
+
+        state.fire(cells[0].0, cells[0].1).unwrap();
+        assert_eq!(state.turn(), Player::Human);
+        assert_eq!(state.hit_streak(), 1);
+        state.fire(cells[1].0, cells[1].1).unwrap();
+        assert_eq!(state.turn(), Player::Ai);
+        assert_eq!(state.hit_streak(), 0);
+    }
+
+    #[test]
+    fn a_salvo_must_be_whole_and_new() {
+        let mut rules = Rules::new();
+        rules.firing = FiringMode::Salvo;
+        let mut state = ready(rules);
+        assert_eq!(state.salvo_size(Player::Human), 5);
+        assert_eq!(state.fire(0, 0), Err("Each turn fires a whole salvo"));
+
+        assert_eq!(
+            state.fire_salvo(&[(0, 0), (1, 0)]),
+            Err("The salvo has the wrong number of shots")
+        );
+        assert_eq!(
+            state.fire_salvo(&[(0, 0), (1, 0), (2, 0), (3, 0), (0, 0)]),
+            Err("The salvo fires at the same cell twice")
+        );
+        assert_eq!(
+            state.fire_salvo(&[(0, 0), (1, 0), (2, 0), (3, 0), (10, 0)]),
+            Err("Position is not on the board")
+        );
+        assert_eq!(state.turn(), Player::Human);
+
+        let shots = state
+            .fire_salvo(&[(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)])
+            .unwrap();
+        assert_eq!(shots.len(), 5);
+    }
+
+    #[test]
+    fn two_players_set_up_in_turn() {
+        let mut state = GameState::with_seed(Rules::new(), 7).unwrap();
+        state.set_opponent(Opponent::Human).unwrap();
+        state.auto_place("uniform").unwrap();
+        state.finish_setup().unwrap();
+        assert_eq!(state.phase(), Phase::Setup);
+        assert_eq!(state.turn(), Player::Ai);
+        assert!(!state.all_ships_placed());
+
+        state.auto_place("uniform").unwrap();
+        state.finish_setup().unwrap();
+        assert_eq!(state.phase(), Phase::Playing);
+        assert_eq!(state.turn(), Player::Human);
+        assert_eq!(state.ai_turn(), Err("There is no AI in this game"));
+
+        let (x, y) = first_water(state.ai_board());
+        state.fire(x, y).unwrap();
+        assert_eq!(state.turn(), Player::Ai);
+        let (x, y) = first_water(state.player_board());
+        assert_eq!(state.fire(x, y), Ok(FireResult::Miss));
+    }
+
+    #[test]
+    fn clearing_takes_back_only_the_current_players_ships() {
+        let mut state = GameState::with_seed(Rules::new(), 7).unwrap();
+        state.set_opponent(Opponent::Human).unwrap();
+        state.auto_place("uniform").unwrap();
+        state.finish_setup().unwrap();
+        state.auto_place("uniform").unwrap();
+
+        state.clear_ships().unwrap();
+        assert!(state.ai_board().get_ships().is_empty());
+        assert_eq!(state.player_board().get_ships().len(), 5);
+        assert_eq!(state.log().get_moves().len(), 5);
+        assert!(state
+            .log()
+            .get_moves()
+            .iter()
+            .all(|game_move| game_move.get_player() == Player::Human));
+
+        state.auto_place("uniform").unwrap();
+        let before = state.ai_board().get_ships().clone();
+        assert_eq!(
+            state.reroll_ships("nowhere"),
+            Err("No placement strategy is registered under this name")
+        );
+        assert_eq!(state.ai_board().get_ships(), &before);
+
+        state.reroll_ships("uniform").unwrap();
+        assert!(state.all_ships_placed());
+        assert_ne!(state.ai_board().get_ships(), &before);
+        assert_eq!(state.log().get_moves().len(), 10);
+    }
+
+    #[test]
+    fn a_lifted_ship_can_be_placed_again() {
+        let mut state = GameState::with_seed(Rules::new(), 7).unwrap();
+        state.auto_place("uniform").unwrap();
+        let (x, y) = first_water(state.player_board());
+        assert_eq!(state.lift_ship(x, y), Err("There is no ship there"));
+
+        let ship = state.player_board().get_ships()[0].clone();
+        assert_eq!(state.lift_ship(ship.x, ship.y), Ok(ship.clone()));
+        assert!(state.player_board().get_ship_at(ship.x, ship.y).is_none());
+        assert_eq!(state.remaining_ships(&ship.ship_type), 1);
+        assert_eq!(state.log().get_moves().len(), 4);
+        assert_eq!(state.finish_setup(), Err("Not all ships have been placed"));
+
+        state
+            .place_ship(&ship.ship_type, ship.x, ship.y, ship.orientation)
+            .unwrap();
+        assert_eq!(state.log().get_moves().len(), 5);
+        state.finish_setup().unwrap();
+        assert_eq!(
+            state.lift_ship(ship.x, ship.y),
+            Err("Ships can only be moved during setup")
+        );
+    }
+
+    #[test]
+    fn remote_shots_are_checked_before_firing() {
+        let mut state = GameState::with_seed(Rules::new(), 7).unwrap();
+        state.set_opponent(Opponent::Remote).unwrap();
+        state.set_first_player(Player::Ai).unwrap();
+        state.auto_place("uniform").unwrap();
+        state.finish_setup().unwrap();
+        assert_eq!(state.turn(), Player::Ai);
+
+        assert_eq!(
+            state.receive_shots(&[(0, u32::MAX)]),
+            Err("Position is not on the board")
+        );
+        let (x, y) = first_water(state.player_board());
+        assert_eq!(
+            state.receive_shots(&[(x, y)]).unwrap()[0].result,
+            FireResult::Miss
+        );
+
+        assert_eq!(
+            state.fire(0, 0),
+            Err("Shots at a remote player are answered over the network")
+        );
+        state
+            .record_shots(&[Shot {
+                x: 0,
+                y: 0,
+                result: FireResult::Miss,
+            }])
+            .unwrap();
+        assert_eq!(
+            state.receive_shots(&[(x, y)]),
+            Err("The salvo fires at a cell that was already fired at")
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    Board::{Board, FireResult},
+    GameState::{Opponent, Player},
+    Rules::Rules,
+    Save::SaveError,
+    Ship::PlacedShip,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Move {
+    Place {
+        player: Player,
+        ship: PlacedShip,
+    },
+    Fire {
+        player: Player,
+        turn: u32,
+        x: u32,
+        y: u32,
+        result: FireResult,
+    },
+}
+
+impl Move {
+    pub fn get_player(&self) -> Player {
+        match self {
+            Self::Place { player, .. } | Self::Fire { player, .. } => *player,
+        }
+    }
+}
+
+/// Every placement and shot of a game, in the order they happened.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MoveLog {
+    rules: Rules,
+    /// Who played the second side, so replays can name the players.
+    #[serde(default)]
+    opponent: Opponent,
+    moves: Vec<Move>,
+}
+
+impl MoveLog {
+    pub fn new(rules: Rules, opponent: Opponent) -> Self {
+        Self {
+            rules,
+            opponent,
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn get_rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn get_opponent(&self) -> Opponent {
+        self.opponent
+    }
+
+    pub fn set_opponent(&mut self, opponent: Opponent) {
+        self.opponent = opponent;
+    }
+
+    pub fn get_moves(&self) -> &Vec<Move> {
+        &self.moves
+    }
+
+    pub fn push(&mut self, game_move: Move) {
+        self.moves.push(game_move);
+    }
+
+    pub fn retain<F>(&mut self, keep: F)
+    where
+        F: FnMut(&Move) -> bool,
+    {
+        self.moves.retain(keep);
+    }
+
+    /// Rebuilds the human and AI boards as they were after the first `count` moves.
+    pub fn replay(&self, count: usize) -> (Board, Board) {
+        let mut human_board = Board::new(&self.rules);
+        let mut ai_board = Board::new(&self.rules);
+
+        for game_move in self.moves.iter().take(count) {
+            match game_move {
+                Move::Place { player, ship } => {
+                    let board = match player {
+                        Player::Human => &mut human_board,
+                        Player::Ai => &mut ai_board,
+                    };
+                    let _ = board.place(&ship.ship_type, ship.x, ship.y, ship.orientation);
+                }
+                Move::Fire { player, x, y, .. } => {
+                    let board = match player {
+                        Player::Human => &mut ai_board,
+                        Player::Ai => &mut human_board,
+                    };
+                    board.fire_at(*x, *y);
+                }
+            }
+        }
+
+        (human_board, ai_board)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SaveError> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SaveError> {
+        let log: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        log.rules.validate().map_err(SaveError::Invalid)?;
+        Ok(log)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameState::GameState;
+
+    /// A two-player game with both fleets placed and a shot fired each.
+    fn two_player_game() -> GameState {
+        let mut state = GameState::with_seed(Rules::new(), 7).unwrap();
+        state.set_opponent(Opponent::Human).unwrap();
+        for _ in 0..2 {
+            state.auto_place("uniform").unwrap();
+            state.finish_setup().unwrap();
+        }
+        for _ in 0..2 {
+            let board = state.board(state.turn().opponent());
+            let (x, y) = (0..10)
+                .flat_map(|y| (0..10).map(move |x| (x, y)))
+                .find(|(x, y)| board.get_ship_at(*x, *y).is_none())
+                .unwrap();
+            state.fire(x, y).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn replay_rebuilds_the_boards_move_by_move() {
+        let state = two_player_game();
+        let log = state.log();
+        assert_eq!(log.get_moves().len(), 12);
+
+        let (human_board, ai_board) = log.replay(5);
+        assert_eq!(human_board.get_ships(), state.player_board().get_ships());
+        assert!(ai_board.get_ships().is_empty());
+
+        let (human_board, ai_board) = log.replay(log.get_moves().len());
+        assert_eq!(ai_board.get_ships(), state.ai_board().get_ships());
+        assert_eq!(ai_board.get_shots(), state.ai_board().get_shots());
+        assert_eq!(human_board.get_shots(), state.player_board().get_shots());
+    }
+
+    #[test]
+    fn a_saved_log_keeps_who_played() {
+        let path =
+            std::env::temp_dir().join(format!("battleships-replay-{}.json", std::process::id()));
+        let state = two_player_game();
+        state.log().save(&path).unwrap();
+        let log = MoveLog::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(log.get_opponent(), Opponent::Human);
+        assert_eq!(log.get_moves(), state.log().get_moves());
+    }
+}
@@ -0,0 +1,382 @@
+use crate::{
+    Board::{Board, FireResult, Shot},
+    Cell::ShotResult,
+    Fleet::FleetConfig,
+    Rules::Rules,
+    Ship::PlacedShip,
+};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    net::TcpStream,
+};
+
+/// Bumped whenever a message changes, so mismatched builds refuse to play.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum NetworkError {
+    Io(io::Error),
+    Closed,
+    Unexpected(String),
+    /// A shot off the board, at a cell that was already fired at, or one too many.
+    InvalidShot(&'static str),
+    VersionMismatch(u32),
+    Rules(&'static str),
+    Cheated(&'static str),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "The connection failed: {}", e),
+            Self::Closed => write!(f, "The other player left the game"),
+            Self::Unexpected(line) => {
+                write!(f, "The other player sent an unexpected message: {}", line)
+            }
+            Self::InvalidShot(e) => write!(f, "The other player fired an invalid shot: {}", e),
+            Self::VersionMismatch(version) => write!(
+                f,
+                "The other player speaks protocol version {}, this game speaks {}",
+                version, PROTOCOL_VERSION
+            ),
+            Self::Rules(e) => write!(f, "The other player's rules are not valid: {}", e),
+            Self::Cheated(e) => write!(f, "The other player's fleet does not check out: {}", e),
+        }
+    }
+}
+
+impl From<io::Error> for NetworkError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// One line of the network protocol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    /// `HELLO <version>`, sent by both sides as soon as they connect.
+    Hello(u32),
+    /// `RULES <json>`, the host's rules for the game.
+    Rules(Rules),
+    /// `COMMIT <sha256>`, the hash of the sender's `REVEAL` text.
+    Commit(String),
+    /// `FIRE <x> <y>`, counted from zero at the top left.
+    Fire(u32, u32),
+    /// `RESULT MISS` or `RESULT HIT`, the answer to a shot that sank nothing.
+    Result(ShotResult),
+    /// `SUNK <ship name>`, the answer to a shot that sank a ship.
+    Sunk(String),
+    /// `RESIGN`, giving the game up.
+    Resign,
+    /// `REVEAL <salt> <ships json>`, the sender's fleet once the game is over.
+    Reveal(String),
+}
+
+impl Message {
+    /// The answer to a shot with this result.
+    pub fn for_result(result: &FireResult) -> Option<Self> {
+        match result {
+            FireResult::Miss => Some(Self::Result(ShotResult::Miss)),
+            FireResult::Hit => Some(Self::Result(ShotResult::Hit)),
+            FireResult::Sunk(ship_type) => Some(Self::Sunk(ship_type.to_string().to_string())),
+            FireResult::AlreadyFired | FireResult::OutOfBounds => None,
+        }
+    }
+
+    /// The result of a shot this message answers, with sunk ships looked up
+    /// in the fleet.
+    pub fn to_result(&self, fleet: &FleetConfig) -> Result<FireResult, NetworkError> {
+        match self {
+            Self::Result(ShotResult::Miss) => Ok(FireResult::Miss),
+            Self::Result(ShotResult::Hit) => Ok(FireResult::Hit),
+            Self::Sunk(name) => fleet
+                .ships()
+                .into_iter()
+                .find(|ship_type| ship_type.to_string() == name)
+                .map(FireResult::Sunk)
+                .ok_or_else(|| NetworkError::Unexpected(self.to_line())),
+            _ => Err(NetworkError::Unexpected(self.to_line())),
+        }
+    }
+
+    pub fn to_line(&self) -> String {
+        match self {
+            Self::Hello(version) => format!("HELLO {}", version),
+            Self::Rules(rules) => {
+                format!("RULES {}", serde_json::to_string(rules).unwrap_or_default())
+            }
+            Self::Commit(hash) => format!("COMMIT {}", hash),
+            Self::Fire(x, y) => format!("FIRE {} {}", x, y),
+            Self::Result(ShotResult::Miss) => "RESULT MISS".to_string(),
+            Self::Result(ShotResult::Hit) => "RESULT HIT".to_string(),
+            Self::Sunk(name) => format!("SUNK {}", name),
+            Self::Resign => "RESIGN".to_string(),
+            Self::Reveal(text) => format!("REVEAL {}", text),
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Self, NetworkError> {
+        let unexpected = || NetworkError::Unexpected(line.to_string());
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+
+        match (command, argument) {
+            ("HELLO", version) => version.parse().map(Self::Hello).map_err(|_| unexpected()),
+            ("RULES", json) => serde_json::from_str(json)
+                .map(Self::Rules)
+                .map_err(|_| unexpected()),
+            ("COMMIT", hash) if !hash.is_empty() => Ok(Self::Commit(hash.to_string())),
+            ("FIRE", position) => {
+                let (x, y) = position.split_once(' ').ok_or_else(unexpected)?;
+                match (x.parse(), y.parse()) {
+                    (Ok(x), Ok(y)) => Ok(Self::Fire(x, y)),
+                    _ => Err(unexpected()),
+                }
+            }
+            ("RESULT", "MISS") => Ok(Self::Result(ShotResult::Miss)),
+            ("RESULT", "HIT") => Ok(Self::Result(ShotResult::Hit)),
+            ("SUNK", name) if !name.is_empty() => Ok(Self::Sunk(name.to_string())),
+            ("RESIGN", "") => Ok(Self::Resign),
+            ("REVEAL", text) if !text.is_empty() => Ok(Self::Reveal(text.to_string())),
+            _ => Err(unexpected()),
+        }
+    }
+}
+
+/// A line based connection to the other player.
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> Result<Self, NetworkError> {
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), NetworkError> {
+        writeln!(self.writer, "{}", message.to_line())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn receive(&mut self) -> Result<Message, NetworkError> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(NetworkError::Closed);
+        }
+        Message::parse(line.trim_end())
+    }
+
+    /// Swaps `HELLO`s and checks both sides speak the same protocol.
+    pub fn handshake(&mut self) -> Result<(), NetworkError> {
+        self.send(&Message::Hello(PROTOCOL_VERSION))?;
+        match self.receive()? {
+            Message::Hello(PROTOCOL_VERSION) => Ok(()),
+            Message::Hello(version) => Err(NetworkError::VersionMismatch(version)),
+            other => Err(NetworkError::Unexpected(other.to_line())),
+        }
+    }
+}
+
+/// The hex encoded SHA-256 hash of `text`.
+fn sha256(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// A fleet kept secret until the end of the game. Its hash is sent when the
+/// game starts and the fleet itself when it ends, so the other player can
+/// check that every shot was answered truthfully.
+pub struct FleetCommitment {
+    reveal: String,
+}
+
+impl FleetCommitment {
+    pub fn new(board: &Board, rng: &mut dyn RngCore) -> Self {
+        // The salt stops the other player guessing the fleet from its hash
+        let mut salt = [0u8; 16];
+        rng.fill_bytes(&mut salt);
+        let salt: String = salt.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let ships = serde_json::to_string(board.get_ships()).unwrap_or_default();
+        Self {
+            reveal: format!("{} {}", salt, ships),
+        }
+    }
+
+    pub fn hash(&self) -> String {
+        sha256(&self.reveal)
+    }
+
+    /// The text sent in `REVEAL` at the end of the game.
+    pub fn reveal(&self) -> &str {
+        &self.reveal
+    }
+}
+
+/// Checks the other player's revealed fleet against the hash they committed
+/// to, the rules, and the results they reported for `shots`. Gives back their
+/// board with the shots fired at it.
+pub fn verify_reveal(
+    rules: &Rules,
+    commitment: &str,
+    reveal: &str,
+    shots: &[Shot],
+) -> Result<Board, NetworkError> {
+    if sha256(reveal) != commitment {
+        return Err(NetworkError::Cheated(
+            "the fleet is not the one committed to",
+        ));
+    }
+    let (_, ships) = reveal
+        .split_once(' ')
+        .ok_or(NetworkError::Cheated("the fleet could not be read"))?;
+    let ships: Vec<PlacedShip> = serde_json::from_str(ships)
+        .map_err(|_| NetworkError::Cheated("the fleet could not be read"))?;
+
+    let mut board = Board::new(rules);
+    for ship in &ships {
+        board
+            .place(&ship.ship_type, ship.x, ship.y, ship.orientation)
+            .map_err(|_| NetworkError::Cheated("the fleet breaks the rules"))?;
+    }
+    if !board.all_ships_placed() {
+        return Err(NetworkError::Cheated("the fleet is missing ships"));
+    }
+
+    for shot in shots {
+        if board.fire_at(shot.x, shot.y) != shot.result {
+            return Err(NetworkError::Cheated("a shot was answered wrongly"));
+        }
+    }
+    Ok(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameState::GameRng, Placement::PlacementStrategy, Placement::UniformPlacement};
+    use rand::SeedableRng;
+
+    #[test]
+    fn messages_survive_a_round_trip() {
+        let messages = [
+            Message::Hello(PROTOCOL_VERSION),
+            Message::Rules(Rules::new()),
+            Message::Commit("abc123".to_string()),
+            Message::Fire(3, 9),
+            Message::Result(ShotResult::Miss),
+            Message::Result(ShotResult::Hit),
+            Message::Sunk("Patrol Boat".to_string()),
+            Message::Resign,
+            Message::Reveal("salt [1, 2]".to_string()),
+        ];
+        for message in messages {
+            assert_eq!(Message::parse(&message.to_line()).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn parse_rejects_malformed_lines() {
+        for line in [
+            "",
+            "HELLO",
+            "HELLO one",
+            "FIRE 3",
+            "FIRE 3 -1",
+            "RESULT SUNK",
+            "SUNK",
+            "RESIGN now",
+            "RULES {}",
+            "WHAT 1 2",
+        ] {
+            assert!(Message::parse(line).is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn parse_checks_the_fleet_in_rules() {
+        let line = Message::Rules(Rules::new()).to_line();
+        assert!(Message::parse(&line.replace("\"size\":2", "\"size\":0")).is_err());
+        assert!(Message::parse(&line.replace("\"count\":1", "\"count\":0")).is_err());
+    }
+
+    #[test]
+    fn results_convert_both_ways() {
+        let fleet = FleetConfig::classic();
+        let carrier = fleet.ships().pop().unwrap();
+        for result in [
+            FireResult::Miss,
+            FireResult::Hit,
+            FireResult::Sunk(carrier.clone()),
+        ] {
+            let message = Message::for_result(&result).unwrap();
+            assert_eq!(message.to_result(&fleet).unwrap(), result);
+        }
+        assert_eq!(Message::for_result(&FireResult::AlreadyFired), None);
+        assert!(Message::Sunk("Rowing Boat".to_string())
+            .to_result(&fleet)
+            .is_err());
+    }
+
+    /// A placed fleet and the shots fired at it, with truthful results.
+    fn fleet_and_shots(rules: &Rules) -> (Board, Vec<Shot>) {
+        let mut rng = GameRng::seed_from_u64(3);
+        let mut board = Board::new(rules);
+        UniformPlacement.place_fleet(&mut board, &mut rng).unwrap();
+
+        let mut fired = board.clone();
+        let shots = (0..rules.width)
+            .map(|x| Shot {
+                x,
+                y: 0,
+                result: fired.fire_at(x, 0),
+            })
+            .collect();
+        (board, shots)
+    }
+
+    #[test]
+    fn verify_reveal_accepts_an_honest_fleet() {
+        let rules = Rules::new();
+        let (board, shots) = fleet_and_shots(&rules);
+        let commitment = FleetCommitment::new(&board, &mut GameRng::seed_from_u64(4));
+
+        let verified =
+            verify_reveal(&rules, &commitment.hash(), commitment.reveal(), &shots).unwrap();
+        assert_eq!(verified.get_ships().len(), board.get_ships().len());
+        assert_eq!(
+            verified.unfired_cells(),
+            board.unfired_cells() - rules.width
+        );
+    }
+
+    #[test]
+    fn verify_reveal_catches_cheating() {
+        let rules = Rules::new();
+        let (board, mut shots) = fleet_and_shots(&rules);
+        let commitment = FleetCommitment::new(&board, &mut GameRng::seed_from_u64(4));
+
+        let other = FleetCommitment::new(&board, &mut GameRng::seed_from_u64(5));
+        assert!(matches!(
+            verify_reveal(&rules, &commitment.hash(), other.reveal(), &shots),
+            Err(NetworkError::Cheated(_))
+        ));
+
+        shots[0].result = match shots[0].result {
+            FireResult::Miss => FireResult::Hit,
+            _ => FireResult::Miss,
+        };
+        assert!(matches!(
+            verify_reveal(&rules, &commitment.hash(), commitment.reveal(), &shots),
+            Err(NetworkError::Cheated("a shot was answered wrongly"))
+        ));
+    }
+}
@@ -0,0 +1,235 @@
+use crate::Game::Game;
+use battleships::{
+    Board::{Board, Selection, Shot},
+    GameState::{Phase, Player},
+    MoveLog::Move,
+    Network::{self, Connection, FleetCommitment, Message, NetworkError},
+    Rules::Rules,
+};
+use colored::{Color, Colorize};
+use std::net::{TcpListener, TcpStream};
+
+/// A game against another computer. Each side keeps its own fleet and only
+/// tells the other one how its shots landed.
+pub struct Online {
+    game: Game,
+    connection: Connection,
+    messages: Vec<String>,
+}
+
+impl Online {
+    /// Picks the rules, then waits for someone to join on `port`. The host fires first.
+    pub fn host(port: u16) -> Result<(), NetworkError> {
+        let mut game = Game::online(Rules::new(), Player::Human).map_err(NetworkError::Rules)?;
+        game.choose_rules();
+
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        println!("Waiting for another player to join on port {}", port);
+        let (stream, address) = listener.accept()?;
+        println!("{} joined", address);
+
+        let mut connection = Connection::new(stream)?;
+        connection.handshake()?;
+        connection.send(&Message::Rules(game.state().rules().clone()))?;
+        Self::play(game, connection)
+    }
+
+    /// Joins a game hosted at `address`, playing by the host's rules.
+    pub fn join(address: &str) -> Result<(), NetworkError> {
+        let mut connection = Connection::new(TcpStream::connect(address)?)?;
+        connection.handshake()?;
+        let rules = match connection.receive()? {
+            Message::Rules(rules) => rules,
+            other => return Err(NetworkError::Unexpected(other.to_line())),
+        };
+        let game = Game::online(rules, Player::Ai).map_err(NetworkError::Rules)?;
+        Self::play(game, connection)
+    }
+
+    fn play(mut game: Game, mut connection: Connection) -> Result<(), NetworkError> {
+        while game.state().phase() == Phase::Setup {
+            game.setup_board();
+        }
+
+        let commitment = FleetCommitment::new(game.state().player_board(), &mut rand::thread_rng());
+        connection.send(&Message::Commit(commitment.hash()))?;
+        println!("Waiting for the other player to place their fleet");
+        let their_commitment = match connection.receive()? {
+            Message::Commit(hash) => hash,
+            other => return Err(NetworkError::Unexpected(other.to_line())),
+        };
+        print!("{}[2J", 27 as char);
+
+        let mut online = Self {
+            game,
+            connection,
+            messages: Vec::new(),
+        };
+        let (winner, resigned) = online.play_turns()?;
+
+        // Both fleets are revealed however the game ended
+        online
+            .connection
+            .send(&Message::Reveal(commitment.reveal().to_string()))?;
+        let reveal = match online.connection.receive()? {
+            Message::Reveal(reveal) => reveal,
+            other => return Err(NetworkError::Unexpected(other.to_line())),
+        };
+        let verified = Network::verify_reveal(
+            online.game.state().rules(),
+            &their_commitment,
+            &reveal,
+            &online.shots_fired(),
+        );
+        online.show_result(winner, resigned, verified);
+        Ok(())
+    }
+
+    /// Plays until someone wins or resigns, giving the winner and whether
+    /// the loser resigned.
+    fn play_turns(&mut self) -> Result<(Player, bool), NetworkError> {
+        let mut cursor = (0, 0);
+        loop {
+            if let Some(winner) = self.game.state().winner() {
+                return Ok((winner, false));
+            }
+            match self.game.state().turn() {
+                Player::Human => match self.game.choose_targets(&mut cursor, &self.messages) {
+                    Some(targets) => self.fire(&targets)?,
+                    None => {
+                        self.connection.send(&Message::Resign)?;
+                        return Ok((Player::Ai, true));
+                    }
+                },
+                Player::Ai => {
+                    if !self.receive_shots()? {
+                        return Ok((Player::Human, true));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends a whole turn of shots, then reads every answer.
+    fn fire(&mut self, targets: &[(u32, u32)]) -> Result<(), NetworkError> {
+        for (x, y) in targets {
+            self.connection.send(&Message::Fire(*x, *y))?;
+        }
+
+        let mut shots = Vec::new();
+        for (x, y) in targets {
+            let result = self
+                .connection
+                .receive()?
+                .to_result(&self.game.state().rules().fleet)?;
+            shots.push(Shot {
+                x: *x,
+                y: *y,
+                result,
+            });
+        }
+
+        self.game
+            .state_mut()
+            .record_shots(&shots)
+            .map_err(|e| NetworkError::Unexpected(e.to_string()))?;
+        self.messages = shots
+            .into_iter()
+            .map(|shot| self.game.describe(Player::Human, shot))
+            .collect();
+        Ok(())
+    }
+
+    /// Reads a whole turn of the other player's shots before answering any of
+    /// them, so a salvo cannot be aimed using its own results. Gives false if
+    /// they resigned.
+    fn receive_shots(&mut self) -> Result<bool, NetworkError> {
+        self.game
+            .show_boards(Player::Human, &Selection::None, &self.messages);
+        println!("Waiting for your opponent to fire");
+
+        let mut targets = Vec::new();
+        for _ in 0..self.game.state().salvo_size(Player::Ai) {
+            match self.connection.receive()? {
+                Message::Fire(x, y) => targets.push((x, y)),
+                Message::Resign => return Ok(false),
+                other => return Err(NetworkError::Unexpected(other.to_line())),
+            }
+        }
+
+        // Every shot is checked before any is answered
+        let shots = self
+            .game
+            .state_mut()
+            .receive_shots(&targets)
+            .map_err(NetworkError::InvalidShot)?;
+        for shot in &shots {
+            let answer = Message::for_result(&shot.result)
+                .ok_or(NetworkError::InvalidShot("the shot could not be answered"))?;
+            self.connection.send(&answer)?;
+        }
+
+        self.messages = shots
+            .into_iter()
+            .map(|shot| self.game.describe(Player::Ai, shot))
+            .collect();
+        print!("{}[2J", 27 as char);
+        Ok(true)
+    }
+
+    /// Every shot this player fired, with the results the other player reported.
+    fn shots_fired(&self) -> Vec<Shot> {
+        self.game
+            .state()
+            .log()
+            .get_moves()
+            .iter()
+            .filter_map(|game_move| match game_move {
+                Move::Fire {
+                    player: Player::Human,
+                    x,
+                    y,
+                    result,
+                    ..
+                } => Some(Shot {
+                    x: *x,
+                    y: *y,
+                    result: result.clone(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn show_result(&self, winner: Player, resigned: bool, verified: Result<Board, NetworkError>) {
+        for message in &self.messages {
+            println!("{}", message);
+        }
+        match (winner, resigned) {
+            (Player::Human, false) => println!("{}", "You Won!".color(Color::Green)),
+            (Player::Human, true) => {
+                println!("{}", "Your opponent resigned, you won!".color(Color::Green))
+            }
+            (Player::Ai, false) => println!("{}", "Your Opponent Won!".color(Color::Red)),
+            (Player::Ai, true) => println!("{}", "You resigned".color(Color::Red)),
+        }
+
+        let state = self.game.state();
+        println!("Your Opponent's Board");
+        match verified {
+            Ok(board) => {
+                board.show(true, &Selection::None);
+                println!(
+                    "{}",
+                    "Their fleet matches every answer they gave".color(Color::Green)
+                );
+            }
+            Err(e) => {
+                state.ai_board().show(false, &Selection::None);
+                println!("{}", e.to_string().color(Color::Red));
+            }
+        }
+        println!("Your Board");
+        state.player_board().show(true, &Selection::None);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::{
+    Board::Board,
+    Ship::{Orientation, PlacedShip},
+    Strategies::ProbabilityStrategy,
+    Targeting::TargetView,
+};
+use rand::{distributions::WeightedIndex, prelude::*};
+
+/// How many times a layout is started again after a ship finds no legal spot.
+const MAX_LAYOUTS: u32 = 20;
+
+/// Decides where ships go when a fleet is placed automatically.
+pub trait PlacementStrategy {
+    /// How strongly to favour a ship covering `cells`, given the ships already
+    /// on the board. Placements weighted zero are never chosen.
+    fn weight(&mut self, board: &Board, cells: &[(u32, u32)]) -> f64;
+
+    /// Places every ship of the fleet that is not on the board yet, keeping
+    /// the ones that are. The board is left unchanged if the fleet does not fit.
+    fn place_fleet(
+        &mut self,
+        board: &mut Board,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), &'static str> {
+        let start = board.clone();
+        let ships = start.remaining_fleet();
+
+        'layout: for _ in 0..MAX_LAYOUTS {
+            *board = start.clone();
+            for ship_type in &ships {
+                let mut options = Vec::new();
+                let mut weights = Vec::new();
+                for y in 0..board.get_height() {
+                    for x in 0..board.get_width() {
+                        for orientation in [Orientation::Horizontal, Orientation::Vertical] {
+                            if board.can_place(ship_type, x, y, orientation).is_err() {
+                                continue;
+                            }
+                            let cells =
+                                PlacedShip::new(ship_type.clone(), x, y, orientation).get_cells();
+                            options.push((x, y, orientation));
+                            weights.push(self.weight(board, &cells).max(0.0));
+                        }
+                    }
+                }
+
+                let Ok(choice) = WeightedIndex::new(&weights) else {
+                    continue 'layout;
+                };
+                let (x, y, orientation) = options[choice.sample(rng)];
+                board.place(ship_type, x, y, orientation).unwrap();
+            }
+            return Ok(());
+        }
+
+        // Weighted choices can keep walking into dead ends on tight boards
+        match start.find_fleet_layout() {
+            Some(layout) => {
+                *board = layout;
+                Ok(())
+            }
+            None => {
+                *board = start;
+                Err("The remaining ships do not fit around the ones already placed")
+            }
+        }
+    }
+}
+
+/// Distance in cells from `cells` to the nearest ship on the board, counting
+/// diagonal steps as one.
+fn distance_to_ships(board: &Board, cells: &[(u32, u32)]) -> Option<u32> {
+    board
+        .get_ships()
+        .iter()
+        .flat_map(|ship| ship.get_cells())
+        .flat_map(|(ship_x, ship_y)| {
+            cells
+                .iter()
+                .map(move |(x, y)| x.abs_diff(ship_x).max(y.abs_diff(ship_y)))
+        })
+        .min()
+}
+
+/// Every legal placement is equally likely.
+pub struct UniformPlacement;
+
+impl PlacementStrategy for UniformPlacement {
+    fn weight(&mut self, _board: &Board, _cells: &[(u32, u32)]) -> f64 {
+        1.0
+    }
+}
+
+/// Favours ships lying along the edges of the board.
+pub struct EdgePlacement;
+
+impl PlacementStrategy for EdgePlacement {
+    fn weight(&mut self, board: &Board, cells: &[(u32, u32)]) -> f64 {
+        let edge_cells = cells
+            .iter()
+            .filter(|(x, y)| {
+                *x == 0 || *y == 0 || *x == board.get_width() - 1 || *y == board.get_height() - 1
+            })
+            .count();
+        (1.0 + edge_cells as f64).powi(3)
+    }
+}
+
+/// Favours ships far away from the ones already placed.
+pub struct SpreadPlacement;
+
+impl PlacementStrategy for SpreadPlacement {
+    fn weight(&mut self, board: &Board, cells: &[(u32, u32)]) -> f64 {
+        match distance_to_ships(board, cells) {
+            Some(distance) => (distance as f64).powi(4),
+            None => 1.0,
+        }
+    }
+}
+
+/// Favours ships close to the ones already placed.
+pub struct ClusteredPlacement;
+
+impl PlacementStrategy for ClusteredPlacement {
+    fn weight(&mut self, board: &Board, cells: &[(u32, u32)]) -> f64 {
+        match distance_to_ships(board, cells) {
+            Some(distance) => 1.0 / (distance.max(1) as f64).powi(4),
+            None => 1.0,
+        }
+    }
+}
+
+/// Favours the cells a probability density hunter fires at last.
+pub struct AntiDensityPlacement {
+    density: Option<Vec<f64>>,
+}
+
+impl AntiDensityPlacement {
+    pub fn new() -> Self {
+        Self { density: None }
+    }
+}
+
+impl Default for AntiDensityPlacement {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlacementStrategy for AntiDensityPlacement {
+    fn weight(&mut self, board: &Board, cells: &[(u32, u32)]) -> f64 {
+        // The hunter sees an untouched board, so the density never changes
+        let density = self.density.get_or_insert_with(|| {
+            let mut empty = board.clone();
+            empty.clear();
+            ProbabilityStrategy::density(&TargetView::from_board(&empty))
+        });
+        let highest = density.iter().cloned().fold(0.0, f64::max);
+        if highest == 0.0 {
+            return 1.0;
+        }
+
+        let mean = cells
+            .iter()
+            .map(|(x, y)| density[(y * board.get_width() + x) as usize])
+            .sum::<f64>()
+            / cells.len() as f64;
+        (highest / mean.max(f64::EPSILON)).powi(4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        GameState::GameRng,
+        Rules::{Adjacency, Rules},
+    };
+
+    fn strategies() -> Vec<Box<dyn PlacementStrategy>> {
+        vec![
+            Box::new(UniformPlacement),
+            Box::new(EdgePlacement),
+            Box::new(SpreadPlacement),
+            Box::new(ClusteredPlacement),
+            Box::new(AntiDensityPlacement::new()),
+        ]
+    }
+
+    /// A board with the destroyer in the top left corner.
+    fn destroyer_board(rules: &Rules) -> Board {
+        let mut board = Board::new(rules);
+        let destroyer = board.get_fleet().ships()[0].clone();
+        board
+            .place(&destroyer, 0, 0, Orientation::Horizontal)
+            .unwrap();
+        board
+    }
+
+    #[test]
+    fn every_placement_fills_the_board_by_the_rules() {
+        let no_touching = Rules {
+            adjacency: Adjacency::NoTouching,
+            ..Rules::new()
+        };
+        for rules in [Rules::new(), no_touching] {
+            for mut strategy in strategies() {
+                for seed in 0..5 {
+                    let mut board = Board::new(&rules);
+                    strategy
+                        .place_fleet(&mut board, &mut GameRng::seed_from_u64(seed))
+                        .unwrap();
+                    assert!(board.all_ships_placed());
+                    // Placing the same ships again checks each against the rules
+                    let mut again = Board::new(&rules);
+                    for ship in board.get_ships() {
+                        assert!(again
+                            .place(&ship.ship_type, ship.x, ship.y, ship.orientation)
+                            .is_ok());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn placing_keeps_the_ships_already_placed() {
+        let rules = Rules::new();
+        for mut strategy in strategies() {
+            let mut board = destroyer_board(&rules);
+            let destroyer = board.get_ships()[0].clone();
+            strategy
+                .place_fleet(&mut board, &mut GameRng::seed_from_u64(1))
+                .unwrap();
+            assert!(board.all_ships_placed());
+            assert_eq!(board.get_ship_at(0, 0), Some(&destroyer));
+        }
+    }
+
+    #[test]
+    fn a_fleet_that_does_not_fit_leaves_the_board_alone() {
+        let rules = Rules {
+            width: 6,
+            height: 6,
+            adjacency: Adjacency::NoTouching,
+            ..Rules::new()
+        };
+        assert!(!Board::new(&rules).can_fit_fleet());
+        for mut strategy in strategies() {
+            let mut board = destroyer_board(&rules);
+            assert!(strategy
+                .place_fleet(&mut board, &mut GameRng::seed_from_u64(1))
+                .is_err());
+            assert_eq!(board.get_ships().len(), 1);
+            assert!(board.get_ship_at(0, 0).is_some());
+        }
+    }
+
+    #[test]
+    fn weights_lean_the_way_each_placement_says() {
+        let rules = Rules::new();
+        let empty = Board::new(&rules);
+        let corner = [(0, 0), (1, 0)];
+        let middle = [(4, 4), (5, 4)];
+        assert!(EdgePlacement.weight(&empty, &corner) > EdgePlacement.weight(&empty, &middle));
+        let mut anti_density = AntiDensityPlacement::new();
+        assert!(anti_density.weight(&empty, &corner) > anti_density.weight(&empty, &middle));
+
+        // The destroyer sits in the top left corner
+        let board = destroyer_board(&rules);
+        let near = [(0, 2), (1, 2)];
+        let far = [(8, 9), (9, 9)];
+        assert!(SpreadPlacement.weight(&board, &far) > SpreadPlacement.weight(&board, &near));
+        assert!(ClusteredPlacement.weight(&board, &near) > ClusteredPlacement.weight(&board, &far));
+    }
+}
@@ -0,0 +1,113 @@
+use battleships::{
+    Board::{Board, FireResult, Selection},
+    GameState::Player,
+    MoveLog::{Move, MoveLog},
+    Save::SaveError,
+};
+use colored::{Color, Colorize};
+use getch_rs::{Getch, Key};
+use std::path::Path;
+
+pub struct Replay {
+    log: MoveLog,
+    position: usize,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, SaveError> {
+        Ok(Self {
+            log: MoveLog::load(path)?,
+            position: 0,
+        })
+    }
+
+    pub fn run(&mut self) {
+        let g = Getch::new();
+        let total = self.log.get_moves().len();
+
+        loop {
+            let (player_board, ai_board) = self.log.replay(self.position);
+
+            let mut player_selection = Selection::None;
+            let mut ai_selection = Selection::None;
+            let last_move = match self.position {
+                0 => None,
+                position => self.log.get_moves().get(position - 1),
+            };
+            if let Some(Move::Fire { player, x, y, .. }) = last_move {
+                let selection = Selection::Cursor { x: *x, y: *y };
+                match player {
+                    Player::Human => ai_selection = selection,
+                    Player::Ai => player_selection = selection,
+                }
+            }
+
+            println!("Move {}/{}", self.position, total);
+            match last_move {
+                Some(game_move) => println!("{}", self.describe_move(game_move)),
+                None => println!("Start of the game"),
+            }
+            println!();
+
+            let opponent = self.log.get_opponent();
+            println!("{} Board", opponent.possessive(Player::Ai));
+            ai_board.show(true, &ai_selection);
+            println!("{} Board", opponent.possessive(Player::Human));
+            player_board.show(true, &player_selection);
+
+            println!();
+
+            println!("Step Backwards/Forwards: Left/Right Arrow Keys");
+            println!("Jump to Start/End: Up/Down Arrow Keys");
+            println!("Quit: Escape Key");
+
+            match g.getch() {
+                Ok(Key::Left) => {
+                    self.position = self.position.saturating_sub(1);
+                }
+                Ok(Key::Right) => {
+                    if self.position < total {
+                        self.position += 1;
+                    }
+                }
+                Ok(Key::Up) => {
+                    self.position = 0;
+                }
+                Ok(Key::Down) => {
+                    self.position = total;
+                }
+                Ok(Key::Esc) => break,
+                Ok(_) => (),
+                Err(e) => println!("{}", e),
+            }
+            print!("{}[2J", 27 as char);
+        }
+    }
+
+    fn describe_move(&self, game_move: &Move) -> String {
+        let name = self.log.get_opponent().player_name(game_move.get_player());
+        match game_move {
+            Move::Place { ship, .. } => format!(
+                "{} placed the {} at {} ({})",
+                name,
+                ship.ship_type.to_string(),
+                Board::position_label(ship.x, ship.y),
+                ship.orientation.to_string()
+            ),
+            Move::Fire {
+                turn, x, y, result, ..
+            } => {
+                let position = Board::position_label(*x, *y);
+                let outcome = match result {
+                    FireResult::Miss => "Miss".to_string(),
+                    FireResult::Hit => "Hit".color(Color::Yellow).to_string(),
+                    FireResult::Sunk(ship_type) => format!("Sunk their {}", ship_type.to_string())
+                        .color(Color::Red)
+                        .to_string(),
+                    FireResult::AlreadyFired | FireResult::OutOfBounds => String::new(),
+                };
+                format!("Turn {}: {} fired at {}: {}", turn, name, position, outcome)
+            }
+        }
+    }
+}
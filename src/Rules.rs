@@ -0,0 +1,196 @@
+use crate::{Board::Board, Fleet::FleetConfig};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub const MIN_BOARD_SIZE: u32 = 6;
+pub const MAX_BOARD_SIZE: u32 = 40;
+
+const ORTHOGONAL_OFFSETS: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const ALL_OFFSETS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// The cells around a cell: the four sharing a side, or all eight.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Neighbourhood {
+    Orthogonal,
+    All,
+}
+
+impl Neighbourhood {
+    pub fn offsets(&self) -> &'static [(i32, i32)] {
+        match self {
+            Self::Orthogonal => &ORTHOGONAL_OFFSETS,
+            Self::All => &ALL_OFFSETS,
+        }
+    }
+
+    /// The cells around the given one that are on a board of this size.
+    pub fn cells(&self, x: u32, y: u32, width: u32, height: u32) -> Vec<(u32, u32)> {
+        self.offsets()
+            .iter()
+            .map(|(offset_x, offset_y)| (x as i32 + offset_x, y as i32 + offset_y))
+            .filter(|(x, y)| *x >= 0 && *y >= 0 && (*x as u32) < width && (*y as u32) < height)
+            .map(|(x, y)| (x as u32, y as u32))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Adjacency {
+    Allowed,
+    NoOrthogonal,
+    NoTouching,
+}
+
+impl Adjacency {
+    pub fn all() -> [Self; 3] {
+        [Self::Allowed, Self::NoOrthogonal, Self::NoTouching]
+    }
+
+    pub fn to_string(&self) -> &'static str {
+        match self {
+            Self::Allowed => "Ships may touch",
+            Self::NoOrthogonal => "Ships may not touch side to side",
+            Self::NoTouching => "Ships may not touch, even diagonally",
+        }
+    }
+
+    /// The cells around a ship that must not hold another ship, if any.
+    pub fn neighbourhood(&self) -> Option<Neighbourhood> {
+        match self {
+            Self::Allowed => None,
+            Self::NoOrthogonal => Some(Neighbourhood::Orthogonal),
+            Self::NoTouching => Some(Neighbourhood::All),
+        }
+    }
+
+    /// The cells next to the given one that the rule keeps clear of other
+    /// ships, on a board of this size.
+    pub fn neighbours(&self, x: u32, y: u32, width: u32, height: u32) -> Vec<(u32, u32)> {
+        self.neighbourhood().map_or(Vec::new(), |neighbourhood| {
+            neighbourhood.cells(x, y, width, height)
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum FiringMode {
+    #[default]
+    Single,
+    Salvo,
+}
+
+impl FiringMode {
+    pub fn all() -> [Self; 2] {
+        [Self::Single, Self::Salvo]
+    }
+
+    pub fn to_string(&self) -> &'static str {
+        match self {
+            Self::Single => "One shot per turn",
+            Self::Salvo => "Salvo: one shot per ship still afloat",
+        }
+    }
+    /// Shots in a turn for a side with `ships_afloat` ships, firing at a
+    /// board with `unfired_cells` cells left.
+    pub fn shots(&self, ships_afloat: u32, unfired_cells: u32) -> u32 {
+        match self {
+            Self::Single => 1,
+            Self::Salvo => ships_afloat.min(unfired_cells).max(1),
+        }
+    }
+}
+
+/// Whether a hit lets the shooter fire again straight away.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum FireAgain {
+    #[default]
+    Never,
+    /// At most this many extra turns in a row.
+    UpTo(u32),
+    Unlimited,
+}
+
+impl FireAgain {
+    pub fn all() -> [Self; 5] {
+        [
+            Self::Never,
+            Self::UpTo(1),
+            Self::UpTo(2),
+            Self::UpTo(3),
+            Self::Unlimited,
+        ]
+    }
+
+    /// Whether a turn with a hit is followed by another one, after `streak`
+    /// extra turns in a row.
+    pub fn allows(&self, streak: u32) -> bool {
+        match self {
+            Self::Never => false,
+            Self::UpTo(cap) => streak < *cap,
+            Self::Unlimited => true,
+        }
+    }
+}
+
+impl fmt::Display for FireAgain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Never => write!(f, "A hit ends the turn"),
+            Self::UpTo(1) => write!(f, "A hit fires again, once per turn"),
+            Self::UpTo(cap) => write!(f, "A hit fires again, up to {} times in a row", cap),
+            Self::Unlimited => write!(f, "A hit always fires again"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Rules {
+    pub width: u32,
+    pub height: u32,
+    pub fleet: FleetConfig,
+    pub adjacency: Adjacency,
+    #[serde(default)]
+    pub firing: FiringMode,
+    #[serde(default)]
+    pub fire_again: FireAgain,
+}
+
+impl Rules {
+    pub fn new() -> Self {
+        Self {
+            width: 10,
+            height: 10,
+            fleet: FleetConfig::classic(),
+            adjacency: Adjacency::Allowed,
+            firing: FiringMode::Single,
+            fire_again: FireAgain::Never,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), &'static str> {
+        for size in [self.width, self.height] {
+            if !(MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&size) {
+                return Err("The board size is out of range");
+            }
+        }
+        if !Board::new(self).can_fit_fleet() {
+            return Err("The fleet does not fit on a board of this size with these rules");
+        }
+        Ok(())
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,162 @@
+use crate::GameState::GameState;
+use std::{fmt, fs, io, path::Path};
+
+pub const SAVE_VERSION: u32 = 4;
+pub const DEFAULT_SAVE_FILE: &str = "battleships.save";
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    UnsupportedVersion(u32),
+    NotSaveable(&'static str),
+    Invalid(&'static str),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Could not access the save file: {}", e),
+            Self::Format(e) => write!(f, "The save file is not valid: {}", e),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "Save files from version {} are not supported", version)
+            }
+            Self::NotSaveable(e) => write!(f, "{}", e),
+            Self::Invalid(e) => write!(f, "The save file is not valid: {}", e),
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Format(e)
+    }
+}
+
+pub fn save_game(state: &GameState, path: &Path) -> Result<(), SaveError> {
+    state.can_save().map_err(SaveError::NotSaveable)?;
+    let save = serde_json::json!({
+        "version": SAVE_VERSION,
+        "game": state,
+    });
+    fs::write(path, serde_json::to_string_pretty(&save)?)?;
+    Ok(())
+}
+
+pub fn load_game(path: &Path) -> Result<GameState, SaveError> {
+    let mut save: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let version = save["version"].as_u64().unwrap_or(0) as u32;
+    if version != SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+    let mut state: GameState = serde_json::from_value(save["game"].take())?;
+    state.check_rules().map_err(SaveError::Invalid)?;
+    state.fall_back_to_difficulty();
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Board::FireResult,
+        Difficulty::Difficulty,
+        GameState::{Opponent, Player},
+        Rules::Rules,
+    };
+    use std::path::PathBuf;
+
+    fn save_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("battleships-{}-{}.save", name, std::process::id()))
+    }
+
+    /// A game with the player's first shot fired, so the AI is to move.
+    fn started() -> GameState {
+        let mut state = GameState::with_seed(Rules::new(), 7).unwrap();
+        state.set_difficulty(Difficulty::Hard).unwrap();
+        state.auto_place("uniform").unwrap();
+        state.finish_setup().unwrap();
+        let (x, y) = (0..10)
+            .flat_map(|y| (0..10).map(move |x| (x, y)))
+            .find(|(x, y)| state.ai_board().get_ship_at(*x, *y).is_none())
+            .unwrap();
+        assert_eq!(state.fire(x, y), Ok(FireResult::Miss));
+        state
+    }
+
+    #[test]
+    fn a_saved_game_carries_on_where_it_stopped() {
+        let path = save_path("round-trip");
+        let mut state = started();
+        save_game(&state, &path).unwrap();
+        let mut loaded = load_game(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.turn(), Player::Ai);
+        assert_eq!(loaded.difficulty(), Difficulty::Hard);
+        assert_eq!(loaded.turn_number(), state.turn_number());
+        assert_eq!(loaded.strategy(), state.strategy());
+        assert_eq!(loaded.log().get_moves(), state.log().get_moves());
+        assert_eq!(
+            loaded.player_board().get_ships(),
+            state.player_board().get_ships()
+        );
+        assert_eq!(loaded.ai_board().get_shots(), state.ai_board().get_shots());
+
+        // The generator is saved too, so the AI fires the same shot
+        assert_eq!(loaded.ai_turn(), state.ai_turn());
+    }
+
+    #[test]
+    fn a_save_that_does_not_hold_together_is_refused() {
+        let path = save_path("edited");
+        save_game(&started(), &path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+
+        let edits = [
+            // A board's size, leaving it too few cells
+            text.replacen("\"width\": 10", "\"width\": 12", 1),
+            // The size in the rules and the log's copy of them, leaving the
+            // boards the wrong size
+            text.split("\"rules\"")
+                .enumerate()
+                .map(|(index, part)| match index {
+                    0 => part.to_string(),
+                    _ => part.replacen("\"width\": 10", "\"width\": 12", 1),
+                })
+                .collect::<Vec<String>>()
+                .join("\"rules\""),
+            text.replacen("\"Ship\": 0", "\"Ship\": 9", 1),
+            text.replacen("\"hits\": [\n", "\"hits\": [\n  true,\n", 1),
+            // One of the player's ships away from the cells that hold it
+            {
+                let mut save: serde_json::Value = serde_json::from_str(&text).unwrap();
+                save["game"]["player_board"]["ships"][0]["x"] = 40.into();
+                serde_json::to_string_pretty(&save).unwrap()
+            },
+        ];
+        for edit in edits {
+            assert_ne!(edit, text);
+            fs::write(&path, edit).unwrap();
+            assert!(load_game(&path).is_err());
+        }
+        fs::remove_file(&path).unwrap();
+    }
+    #[test]
+    fn network_games_are_not_saved() {
+        let path = save_path("remote");
+        let mut state = GameState::with_seed(Rules::new(), 7).unwrap();
+        state.set_opponent(Opponent::Remote).unwrap();
+        assert!(matches!(
+            save_game(&state, &path),
+            Err(SaveError::NotSaveable(_))
+        ));
+        assert!(!path.exists());
+    }
+}
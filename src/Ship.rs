@@ -1,67 +1,102 @@
-use num_derive::{FromPrimitive, ToPrimitive};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy)]
-pub struct Ship {
-    pub ship_type: ShipType,
-    pub damaged: bool,
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ShipType {
+    name: String,
+    label: String,
+    size: u32,
 }
 
-impl Ship {
-    pub fn new() -> Self {
+impl ShipType {
+    pub fn new(name: &str, label: &str, size: u32) -> Self {
         Self {
-            ship_type: ShipType::Empty,
-            damaged: false,
+            name: name.to_string(),
+            label: label.to_string(),
+            size,
         }
     }
+
+    pub fn to_string(&self) -> &str {
+        &self.name
+    }
+
+    pub fn to_shorten(&self) -> &str {
+        &self.label
+    }
+
+    pub fn get_size(&self) -> u32 {
+        self.size
+    }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-pub enum ShipType {
-    Empty = 0,
-    Destroyer = 1,
-    Submarine = 2,
-    Cruiser = 3,
-    Battleship = 4,
-    Carrier = 5,
-    Shot = 6,
-    Selection = 7,
-    WaterSelection = 8,
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
 }
 
-impl ShipType {
-    pub fn to_string(&self) -> &str {
+impl Orientation {
+    pub fn rotate(&self) -> Self {
         match self {
-            Self::Destroyer => "Destroyer",
-            Self::Submarine => "Submarine",
-            Self::Cruiser => "Cruiser",
-            Self::Battleship => "Battleship",
-            Self::Carrier => "Carrier",
-            _ => "",
+            Self::Horizontal => Self::Vertical,
+            Self::Vertical => Self::Horizontal,
         }
     }
 
-    pub fn to_shorten(&self) -> &str {
+    pub fn to_string(&self) -> &'static str {
         match self {
-            Self::Destroyer => "des",
-            Self::Submarine => "sub",
-            Self::Cruiser => "cru",
-            Self::Battleship => "bat",
-            Self::Carrier => "car",
-            Self::Shot => " x ",
-            Self::Selection => " + ",
-            Self::WaterSelection => " + ",
-            _ => "",
+            Self::Horizontal => "Horizontal",
+            Self::Vertical => "Vertical",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct PlacedShip {
+    pub ship_type: ShipType,
+    pub x: u32,
+    pub y: u32,
+    pub orientation: Orientation,
+    pub hits: Vec<bool>,
+}
+
+impl PlacedShip {
+    pub fn new(ship_type: ShipType, x: u32, y: u32, orientation: Orientation) -> Self {
+        Self {
+            hits: vec![false; ship_type.get_size() as usize],
+            ship_type,
+            x,
+            y,
+            orientation,
         }
     }
 
-    pub fn get_size(&self) -> u32 {
-        match self {
-            Self::Destroyer => 2,
-            Self::Submarine => 3,
-            Self::Cruiser => 3,
-            Self::Battleship => 4,
-            Self::Carrier => 5,
-            _ => 0,
+    pub fn get_cells(&self) -> Vec<(u32, u32)> {
+        (0..self.ship_type.get_size())
+            .map(|i| match self.orientation {
+                Orientation::Horizontal => (self.x + i, self.y),
+                Orientation::Vertical => (self.x, self.y + i),
+            })
+            .collect()
+    }
+
+    pub fn covers(&self, x: u32, y: u32) -> bool {
+        let size = self.ship_type.get_size();
+        match self.orientation {
+            Orientation::Horizontal => y == self.y && x >= self.x && x - self.x < size,
+            Orientation::Vertical => x == self.x && y >= self.y && y - self.y < size,
         }
     }
+
+    pub fn hit(&mut self, x: u32, y: u32) {
+        let index = match self.orientation {
+            Orientation::Horizontal => x - self.x,
+            Orientation::Vertical => y - self.y,
+        };
+        self.hits[index as usize] = true;
+    }
+
+    pub fn is_sunk(&self) -> bool {
+        self.hits.iter().all(|hit| *hit)
+    }
 }
@@ -0,0 +1,264 @@
+use battleships::{
+    Difficulty::Difficulty,
+    Engine::Engine,
+    GameState::GameRng,
+    Rules::{FireAgain, FiringMode, Rules},
+    Simulation::{self, Contestant, MatchSummary},
+    Targeting::StrategyRegistry,
+};
+use rand::SeedableRng;
+
+pub const USAGE: &str = "battleships simulate [--games <n>] [--seed <n>] [--format text|csv|json] \
+                         [--salvo] [--fire-again <n>|unlimited] [--engine <name>=<command>...] \
+                         (<ai> <ai> | --tournament [<ai>...]), where <ai> is a difficulty, strategy \
+                         or engine name with an optional :<placement>";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+/// Plays AI against AI without a terminal and prints the statistics.
+pub struct Simulate {
+    games: u32,
+    seed: u64,
+    format: Format,
+    tournament: bool,
+    rules: Rules,
+    contestants: Vec<Contestant>,
+    registry: StrategyRegistry,
+}
+
+impl Simulate {
+    pub fn from_args(args: &[String]) -> Result<Self, &'static str> {
+        let mut simulate = Self {
+            games: 100,
+            seed: rand::random(),
+            format: Format::Text,
+            tournament: false,
+            rules: Rules::new(),
+            contestants: Vec::new(),
+            registry: StrategyRegistry::new(),
+        };
+
+        let mut names = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--games" => {
+                    simulate.games = args
+                        .next()
+                        .and_then(|games| games.parse().ok())
+                        .ok_or("--games needs a whole number")?;
+                }
+                "--seed" => {
+                    simulate.seed = args
+                        .next()
+                        .and_then(|seed| seed.parse().ok())
+                        .ok_or("--seed needs a whole number")?;
+                }
+                "--format" => {
+                    simulate.format = match args.next().map(|format| format.as_str()) {
+                        Some("text") => Format::Text,
+                        Some("csv") => Format::Csv,
+                        Some("json") => Format::Json,
+                        _ => return Err("--format needs text, csv or json"),
+                    };
+                }
+                "--tournament" => simulate.tournament = true,
+                "--salvo" => simulate.rules.firing = FiringMode::Salvo,
+                "--fire-again" => {
+                    simulate.rules.fire_again = match args.next().map(|cap| cap.as_str()) {
+                        Some("unlimited") => FireAgain::Unlimited,
+                        Some(cap) => FireAgain::UpTo(
+                            cap.parse()
+                                .map_err(|_| "--fire-again needs a whole number or unlimited")?,
+                        ),
+                        None => return Err("--fire-again needs a whole number or unlimited"),
+                    };
+                }
+                "--engine" => {
+                    let (name, command) = args
+                        .next()
+                        .and_then(|engine| engine.split_once('='))
+                        .ok_or("--engine needs <name>=<command>")?;
+                    if let Err(e) = Engine::launch(command) {
+                        eprintln!("{}", e);
+                        return Err("An engine could not be started");
+                    }
+                    simulate.registry.register_engine(name, command);
+                }
+                name => names.push(name),
+            }
+        }
+
+        // Engines may be named before the option registering them
+        for name in names {
+            let contestant = simulate.contestant(name)?;
+            simulate.contestants.push(contestant);
+        }
+
+        if simulate.tournament && simulate.contestants.is_empty() {
+            for difficulty in Difficulty::all() {
+                let contestant = simulate.contestant(difficulty.to_string())?;
+                simulate.contestants.push(contestant);
+            }
+        }
+        if simulate.tournament && simulate.contestants.len() < 2 {
+            return Err("A tournament needs at least two AIs");
+        }
+        if !simulate.tournament && simulate.contestants.len() != 2 {
+            return Err("Give exactly two AIs, or use --tournament");
+        }
+        Ok(simulate)
+    }
+
+    /// An AI named by a difficulty or by a registered strategy, optionally
+    /// followed by a colon and a placement strategy.
+    fn contestant(&self, name: &str) -> Result<Contestant, &'static str> {
+        let (targeting, placement) = match name.split_once(':') {
+            Some((targeting, placement)) => (targeting, Some(placement)),
+            None => (name, None),
+        };
+
+        let mut contestant = match Difficulty::all()
+            .into_iter()
+            .find(|difficulty| difficulty.to_string().eq_ignore_ascii_case(targeting))
+        {
+            Some(difficulty) => Contestant::new(
+                difficulty.to_string(),
+                self.registry.strategy_for(difficulty),
+                self.registry.placement_for(difficulty),
+            ),
+            None if self.registry.contains(targeting) => {
+                // Engines place their own fleets
+                let placement = match self.registry.contains_placement(targeting) {
+                    true => targeting,
+                    false => "uniform",
+                };
+                Contestant::new(targeting, targeting, placement)
+            }
+            None => return Err("Unknown AI, use a difficulty or a strategy name"),
+        };
+
+        if let Some(placement) = placement {
+            if !self.registry.contains_placement(placement) {
+                return Err("Unknown placement strategy");
+            }
+            contestant.placement = placement.to_string();
+            contestant.name = name.to_string();
+        }
+        Ok(contestant)
+    }
+
+    pub fn run(&self) -> Result<(), &'static str> {
+        let mut rng = GameRng::seed_from_u64(self.seed);
+        let summaries = Simulation::round_robin(
+            &self.rules,
+            &self.registry,
+            &self.contestants,
+            self.games,
+            &mut rng,
+        )?;
+
+        match self.format {
+            Format::Text => self.print_text(&summaries),
+            Format::Csv => self.print_csv(&summaries),
+            Format::Json => {
+                let output = serde_json::json!({
+                    "seed": self.seed,
+                    "matches": summaries,
+                });
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&output).map_err(|_| "Could not write JSON")?
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn print_text(&self, summaries: &[MatchSummary]) {
+        println!("Seed: {}", self.seed);
+        for summary in summaries {
+            println!();
+            println!(
+                "{} ({}, {}) vs {} ({}, {}), {} games",
+                summary.contestants[0].name,
+                summary.contestants[0].strategy,
+                summary.contestants[0].placement,
+                summary.contestants[1].name,
+                summary.contestants[1].strategy,
+                summary.contestants[1].placement,
+                summary.games
+            );
+            for side in 0..2 {
+                let (low, high) = summary.win_rate_cis[side];
+                println!(
+                    "  {}: {} wins ({:.1}%, 95% CI {:.1}%-{:.1}%)",
+                    summary.contestants[side].name,
+                    summary.wins[side],
+                    summary.win_rates[side] * 100.0,
+                    low * 100.0,
+                    high * 100.0
+                );
+                let shots = &summary.shots_to_win[side];
+                if shots.games > 0 {
+                    println!(
+                        "    Shots to win: mean {:.1} (95% CI {:.1}-{:.1}), median {:.1}, p10 {:.1}, p90 {:.1}",
+                        shots.mean,
+                        shots.mean_ci.0,
+                        shots.mean_ci.1,
+                        shots.median,
+                        shots.p10,
+                        shots.p90
+                    );
+                }
+            }
+        }
+    }
+
+    fn print_csv(&self, summaries: &[MatchSummary]) {
+        println!(
+            "seed,ai,strategy,placement,opponent,opponent_strategy,opponent_placement,games,wins,win_rate,win_rate_low,win_rate_high,\
+             mean_shots,mean_shots_low,mean_shots_high,median_shots,p10_shots,p90_shots"
+        );
+        for summary in summaries {
+            for side in 0..2 {
+                let opponent = &summary.contestants[1 - side];
+                let shots = &summary.shots_to_win[side];
+                println!(
+                    "{},{},{},{},{},{},{},{},{},{:.4},{:.4},{:.4},{:.2},{:.2},{:.2},{:.1},{:.1},{:.1}",
+                    self.seed,
+                    csv_field(&summary.contestants[side].name),
+                    csv_field(&summary.contestants[side].strategy),
+                    csv_field(&summary.contestants[side].placement),
+                    csv_field(&opponent.name),
+                    csv_field(&opponent.strategy),
+                    csv_field(&opponent.placement),
+                    summary.games,
+                    summary.wins[side],
+                    summary.win_rates[side],
+                    summary.win_rate_cis[side].0,
+                    summary.win_rate_cis[side].1,
+                    shots.mean,
+                    shots.mean_ci.0,
+                    shots.mean_ci.1,
+                    shots.median,
+                    shots.p10,
+                    shots.p90
+                );
+            }
+        }
+    }
+}
+
+/// Quotes a CSV field if it holds a comma, a quote or a line break.
+fn csv_field(text: &str) -> String {
+    match text.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_string(),
+    }
+}
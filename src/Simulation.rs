@@ -0,0 +1,300 @@
+use crate::{
+    Board::Board,
+    Rules::{FiringMode, Rules},
+    Targeting::StrategyRegistry,
+    AI::AI,
+};
+use rand::RngCore;
+use serde::Serialize;
+
+/// The z score of a 95% confidence interval.
+const Z_95: f64 = 1.96;
+
+/// One side of a simulated game: a display name, the strategy it fires with
+/// and the one it places its fleet with.
+#[derive(Clone, Debug, Serialize)]
+pub struct Contestant {
+    pub name: String,
+    pub strategy: String,
+    pub placement: String,
+}
+
+impl Contestant {
+    pub fn new(name: &str, strategy: &str, placement: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            strategy: strategy.to_string(),
+            placement: placement.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct GameResult {
+    /// Index of the winning contestant.
+    pub winner: usize,
+    /// Shots the winner fired.
+    pub shots: u32,
+}
+
+/// Summary statistics of how many shots a contestant needed to win.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ShotStats {
+    pub games: u32,
+    pub mean: f64,
+    pub median: f64,
+    pub p10: f64,
+    pub p90: f64,
+    /// 95% confidence interval of the mean.
+    pub mean_ci: (f64, f64),
+}
+
+impl ShotStats {
+    pub fn from_shots(shots: &[u32]) -> Self {
+        if shots.is_empty() {
+            return Self::default();
+        }
+
+        let mut sorted = shots.to_vec();
+        sorted.sort_unstable();
+        let count = sorted.len() as f64;
+        let mean = sorted.iter().map(|shots| *shots as f64).sum::<f64>() / count;
+        let variance = match sorted.len() {
+            1 => 0.0,
+            _ => {
+                sorted
+                    .iter()
+                    .map(|shots| (*shots as f64 - mean).powi(2))
+                    .sum::<f64>()
+                    / (count - 1.0)
+            }
+        };
+        let margin = Z_95 * (variance / count).sqrt();
+
+        Self {
+            games: sorted.len() as u32,
+            mean,
+            median: percentile(&sorted, 50.0),
+            p10: percentile(&sorted, 10.0),
+            p90: percentile(&sorted, 90.0),
+            mean_ci: (mean - margin, mean + margin),
+        }
+    }
+}
+
+/// Linearly interpolated percentile of sorted values.
+fn percentile(sorted: &[u32], percent: f64) -> f64 {
+    let rank = percent / 100.0 * (sorted.len() - 1) as f64;
+    let low = rank.floor() as usize;
+    let high = rank.ceil() as usize;
+    sorted[low] as f64 + (sorted[high] as f64 - sorted[low] as f64) * (rank - low as f64)
+}
+
+/// 95% Wilson score interval of a win rate.
+fn wilson_interval(wins: u32, games: u32) -> (f64, f64) {
+    if games == 0 {
+        return (0.0, 0.0);
+    }
+    let games = games as f64;
+    let rate = wins as f64 / games;
+    let z_squared = Z_95 * Z_95;
+    let center = (rate + z_squared / (2.0 * games)) / (1.0 + z_squared / games);
+    let margin = Z_95 / (1.0 + z_squared / games)
+        * (rate * (1.0 - rate) / games + z_squared / (4.0 * games * games)).sqrt();
+    ((center - margin).max(0.0), (center + margin).min(1.0))
+}
+
+/// The results of a series of games between two contestants.
+#[derive(Clone, Debug, Serialize)]
+pub struct MatchSummary {
+    pub contestants: [Contestant; 2],
+    pub games: u32,
+    pub wins: [u32; 2],
+    /// Share of games won by each contestant.
+    pub win_rates: [f64; 2],
+    /// 95% confidence interval of each win rate.
+    pub win_rate_cis: [(f64, f64); 2],
+    pub shots_to_win: [ShotStats; 2],
+}
+
+/// Plays one game between two AIs without any terminal output. `first` is
+/// the index of the contestant that fires first.
+pub fn play_game(
+    rules: &Rules,
+    registry: &StrategyRegistry,
+    contestants: &[Contestant; 2],
+    first: usize,
+    rng: &mut dyn RngCore,
+) -> Result<GameResult, &'static str> {
+    rules.validate()?;
+
+    let mut ais = [
+        AI::new(&contestants[0].strategy, &contestants[0].placement),
+        AI::new(&contestants[1].strategy, &contestants[1].placement),
+    ];
+    let mut boards = [Board::new(rules), Board::new(rules)];
+    for (ai, board) in ais.iter().zip(boards.iter_mut()) {
+        ai.place_ships(board, registry, rng)?;
+    }
+
+    let mut shots = [0, 0];
+    let mut shooter = first;
+    let mut hit_streak = 0;
+    loop {
+        let salvo_size = boards[shooter].ships_afloat();
+        let target = &mut boards[1 - shooter];
+        let turn_shots = match rules.firing {
+            FiringMode::Single => vec![ais[shooter].do_turn(target, registry, rng)?],
+            FiringMode::Salvo => ais[shooter].do_salvo(target, salvo_size, registry, rng)?,
+        };
+        if !turn_shots.iter().all(|shot| shot.result.is_valid_shot()) {
+            return Err("An AI ran out of cells to fire at");
+        }
+        shots[shooter] += turn_shots.len() as u32;
+
+        if target.all_destroyed() {
+            return Ok(GameResult {
+                winner: shooter,
+                shots: shots[shooter],
+            });
+        }
+
+        let hit = turn_shots.iter().any(|shot| shot.result.is_hit());
+        if hit && rules.fire_again.allows(hit_streak) {
+            hit_streak += 1;
+        } else {
+            hit_streak = 0;
+            shooter = 1 - shooter;
+        }
+    }
+}
+
+/// Plays `games` games between two contestants, taking turns to go first.
+pub fn run_match(
+    rules: &Rules,
+    registry: &StrategyRegistry,
+    contestants: &[Contestant; 2],
+    games: u32,
+    rng: &mut dyn RngCore,
+) -> Result<MatchSummary, &'static str> {
+    for contestant in contestants {
+        if !registry.contains(&contestant.strategy) {
+            return Err("No strategy is registered under this name");
+        }
+        if !registry.contains_placement(&contestant.placement) {
+            return Err("No placement strategy is registered under this name");
+        }
+    }
+
+    let mut shots: [Vec<u32>; 2] = [Vec::new(), Vec::new()];
+    for game in 0..games {
+        let result = play_game(rules, registry, contestants, (game % 2) as usize, rng)?;
+        shots[result.winner].push(result.shots);
+    }
+
+    let wins = [shots[0].len() as u32, shots[1].len() as u32];
+    let win_rate = |wins: u32| match games {
+        0 => 0.0,
+        _ => wins as f64 / games as f64,
+    };
+    Ok(MatchSummary {
+        contestants: contestants.clone(),
+        games,
+        wins,
+        win_rates: [win_rate(wins[0]), win_rate(wins[1])],
+        win_rate_cis: [
+            wilson_interval(wins[0], games),
+            wilson_interval(wins[1], games),
+        ],
+        shots_to_win: [
+            ShotStats::from_shots(&shots[0]),
+            ShotStats::from_shots(&shots[1]),
+        ],
+    })
+}
+
+/// Plays a match between every pair of contestants.
+pub fn round_robin(
+    rules: &Rules,
+    registry: &StrategyRegistry,
+    contestants: &[Contestant],
+    games: u32,
+    rng: &mut dyn RngCore,
+) -> Result<Vec<MatchSummary>, &'static str> {
+    let mut summaries = Vec::new();
+    for (index, first) in contestants.iter().enumerate() {
+        for second in &contestants[index + 1..] {
+            let pair = [first.clone(), second.clone()];
+            summaries.push(run_match(rules, registry, &pair, games, rng)?);
+        }
+    }
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameState::GameRng;
+    use rand::SeedableRng;
+
+    fn close(value: f64, expected: f64) -> bool {
+        (value - expected).abs() < 0.001
+    }
+
+    #[test]
+    fn shot_stats_summarise_the_shots() {
+        let stats = ShotStats::from_shots(&[50, 10, 40, 20, 30]);
+        assert_eq!(stats.games, 5);
+        assert!(close(stats.mean, 30.0));
+        assert!(close(stats.median, 30.0));
+        assert!(close(stats.p10, 14.0));
+        assert!(close(stats.p90, 46.0));
+        // Standard deviation 15.81, so the margin is 1.96 * 15.81 / sqrt(5)
+        assert!(close(stats.mean_ci.0, 16.141));
+        assert!(close(stats.mean_ci.1, 43.859));
+    }
+
+    #[test]
+    fn shot_stats_handle_few_games() {
+        assert_eq!(ShotStats::from_shots(&[]).games, 0);
+
+        let stats = ShotStats::from_shots(&[42]);
+        assert!(close(stats.mean, 42.0));
+        assert!(close(stats.p10, 42.0));
+        assert_eq!(stats.mean_ci, (42.0, 42.0));
+    }
+
+    #[test]
+    fn wilson_interval_stays_within_bounds() {
+        assert_eq!(wilson_interval(0, 0), (0.0, 0.0));
+
+        let (low, high) = wilson_interval(50, 100);
+        assert!(close(low, 0.404));
+        assert!(close(high, 0.596));
+
+        let (low, high) = wilson_interval(0, 10);
+        assert!(close(low, 0.0));
+        assert!(close(high, 0.278));
+        let (low, high) = wilson_interval(10, 10);
+        assert!(close(low, 0.722));
+        assert!(close(high, 1.0));
+    }
+
+    #[test]
+    fn run_match_plays_every_game() {
+        let registry = StrategyRegistry::new();
+        let contestants = [
+            Contestant::new("Easy", "random", "uniform"),
+            Contestant::new("Hard", "hunt-target-parity", "spread-out"),
+        ];
+        let mut rng = GameRng::seed_from_u64(1);
+        let summary = run_match(&Rules::new(), &registry, &contestants, 10, &mut rng).unwrap();
+        assert_eq!(summary.wins[0] + summary.wins[1], 10);
+
+        let unknown = [
+            Contestant::new("Easy", "random", "uniform"),
+            Contestant::new("Nobody", "nobody", "uniform"),
+        ];
+        assert!(run_match(&Rules::new(), &registry, &unknown, 1, &mut rng).is_err());
+    }
+}
@@ -0,0 +1,416 @@
+use crate::{
+    Rules::Neighbourhood,
+    Targeting::{Knowledge, TargetView, TargetingStrategy},
+};
+use rand::prelude::*;
+
+/// A random cell out of `options`, or any unknown cell if there are none.
+fn pick(options: &[(u32, u32)], view: &TargetView, rng: &mut dyn RngCore) -> (u32, u32) {
+    match options.choose(rng) {
+        Some(target) => *target,
+        None => RandomStrategy.next_target(view, rng),
+    }
+}
+
+/// The cell one step from (x, y) in the given direction, if it is on the board.
+fn step(view: &TargetView, x: u32, y: u32, (step_x, step_y): (i32, i32)) -> Option<(u32, u32)> {
+    let x = x.checked_add_signed(step_x)?;
+    let y = y.checked_add_signed(step_y)?;
+    view.get(x, y).map(|_| (x, y))
+}
+
+/// Fires at any unknown cell.
+pub struct RandomStrategy;
+
+impl TargetingStrategy for RandomStrategy {
+    fn next_target(&mut self, view: &TargetView, rng: &mut dyn RngCore) -> (u32, u32) {
+        match view.unknown_cells().choose(rng) {
+            Some(target) => *target,
+            None => (0, 0),
+        }
+    }
+}
+
+/// Fires next to a hit on a ship that is still afloat.
+pub struct SmartStrategy;
+
+impl TargetingStrategy for SmartStrategy {
+    fn next_target(&mut self, view: &TargetView, rng: &mut dyn RngCore) -> (u32, u32) {
+        let options: Vec<(u32, u32)> = view
+            .hits()
+            .into_iter()
+            .flat_map(|(x, y)| view.neighbours(x, y, Neighbourhood::All))
+            .filter(|(x, y)| view.is_unknown(*x, *y))
+            .collect();
+        pick(&options, view, rng)
+    }
+}
+
+/// Fires on a checkerboard-like pattern spaced by the smallest ship still
+/// afloat, since every remaining ship must cover one of its cells.
+pub struct ParityStrategy;
+
+impl ParityStrategy {
+    /// The unknown cells of the pattern that has already had the most shots.
+    pub fn candidates(view: &TargetView) -> Vec<(u32, u32)> {
+        let spacing = view
+            .remaining_ships()
+            .iter()
+            .map(|ship_type| ship_type.get_size())
+            .min()
+            .unwrap_or(1)
+            .max(1);
+
+        (0..spacing)
+            .map(|offset| {
+                view.unknown_cells()
+                    .into_iter()
+                    .filter(|(x, y)| (x + y) % spacing == offset)
+                    .collect::<Vec<(u32, u32)>>()
+            })
+            .filter(|cells| !cells.is_empty())
+            .min_by_key(|cells| cells.len())
+            .unwrap_or_default()
+    }
+}
+
+impl TargetingStrategy for ParityStrategy {
+    fn next_target(&mut self, view: &TargetView, rng: &mut dyn RngCore) -> (u32, u32) {
+        pick(&Self::candidates(view), view, rng)
+    }
+}
+
+/// Hunts with another strategy until it hits, then probes the hit's four
+/// neighbours and follows the line of hits both ways until the ship sinks.
+pub struct HuntTargetStrategy {
+    hunt: Box<dyn TargetingStrategy>,
+}
+
+impl HuntTargetStrategy {
+    pub fn new(hunt: Box<dyn TargetingStrategy>) -> Self {
+        Self { hunt }
+    }
+
+    /// The unknown cells just past each end of every line of two or more hits.
+    fn line_ends(view: &TargetView, hits: &[(u32, u32)]) -> Vec<(u32, u32)> {
+        let mut ends = Vec::new();
+        for &(x, y) in hits {
+            for direction in [(1, 0), (0, 1)] {
+                let next = step(view, x, y, direction).and_then(|(x, y)| view.get(x, y));
+                if next != Some(Knowledge::Hit) {
+                    continue;
+                }
+                for direction in [direction, (-direction.0, -direction.1)] {
+                    let mut current = (x, y);
+                    while let Some((next_x, next_y)) = step(view, current.0, current.1, direction) {
+                        if view.get(next_x, next_y) != Some(Knowledge::Hit) {
+                            if view.is_unknown(next_x, next_y) {
+                                ends.push((next_x, next_y));
+                            }
+                            break;
+                        }
+                        current = (next_x, next_y);
+                    }
+                }
+            }
+        }
+        ends
+    }
+}
+
+impl TargetingStrategy for HuntTargetStrategy {
+    fn next_target(&mut self, view: &TargetView, rng: &mut dyn RngCore) -> (u32, u32) {
+        let hits = view.hits();
+        if hits.is_empty() {
+            return self.hunt.next_target(view, rng);
+        }
+
+        let ends = Self::line_ends(view, &hits);
+        if let Some(target) = ends.choose(rng) {
+            return *target;
+        }
+
+        // Either a lone hit, or a line blocked at both ends because it runs
+        // across several ships, so try every side of every hit
+        let around: Vec<(u32, u32)> = hits
+            .iter()
+            .flat_map(|(x, y)| view.neighbours(*x, *y, Neighbourhood::Orthogonal))
+            .filter(|(x, y)| view.is_unknown(*x, *y))
+            .collect();
+        match around.choose(rng) {
+            Some(target) => *target,
+            None => self.hunt.next_target(view, rng),
+        }
+    }
+}
+
+/// How much more likely a placement becomes for each known hit it covers.
+const HIT_WEIGHT: f64 = 25.0;
+
+/// Fires where the most legal placements of the remaining ships overlap,
+/// strongly favouring placements that run through known hits.
+pub struct ProbabilityStrategy;
+
+impl ProbabilityStrategy {
+    /// For every cell, row by row, the weighted number of placements covering it.
+    pub fn density(view: &TargetView) -> Vec<f64> {
+        let width = view.get_width();
+        let height = view.get_height();
+        let mut density = vec![0.0; (width * height) as usize];
+
+        for ship_type in view.remaining_ships() {
+            let size = ship_type.get_size();
+            for (step_x, step_y) in [(1, 0), (0, 1)] {
+                for y in 0..height {
+                    for x in 0..width {
+                        if x + (size - 1) * step_x >= width || y + (size - 1) * step_y >= height {
+                            continue;
+                        }
+                        let cells: Vec<(u32, u32)> = (0..size)
+                            .map(|i| (x + i * step_x, y + i * step_y))
+                            .collect();
+
+                        let mut hits = 0;
+                        let mut legal = true;
+                        for (cell_x, cell_y) in &cells {
+                            match view.get(*cell_x, *cell_y) {
+                                Some(Knowledge::Unknown) => {}
+                                Some(Knowledge::Hit) => hits += 1,
+                                _ => legal = false,
+                            }
+                        }
+                        if !legal {
+                            continue;
+                        }
+
+                        let weight = HIT_WEIGHT.powi(hits);
+                        for (cell_x, cell_y) in cells {
+                            density[(cell_y * width + cell_x) as usize] += weight;
+                        }
+                    }
+                }
+            }
+        }
+        density
+    }
+}
+
+impl TargetingStrategy for ProbabilityStrategy {
+    fn next_target(&mut self, view: &TargetView, rng: &mut dyn RngCore) -> (u32, u32) {
+        let density = Self::density(view);
+        let width = view.get_width();
+
+        let mut best = Vec::new();
+        let mut best_density = 0.0;
+        for (x, y) in view.unknown_cells() {
+            let cell_density = density[(y * width + x) as usize];
+            if cell_density > best_density {
+                best_density = cell_density;
+                best.clear();
+            }
+            if cell_density == best_density && cell_density > 0.0 {
+                best.push((x, y));
+            }
+        }
+        pick(&best, view, rng)
+    }
+}
+
+/// Fires at the corners of the board.
+pub struct CornerStrategy;
+
+impl TargetingStrategy for CornerStrategy {
+    fn next_target(&mut self, view: &TargetView, rng: &mut dyn RngCore) -> (u32, u32) {
+        let right = view.get_width() - 1;
+        let bottom = view.get_height() - 1;
+        let options: Vec<(u32, u32)> = [(0, 0), (right, 0), (0, bottom), (right, bottom)]
+            .into_iter()
+            .filter(|(x, y)| view.is_unknown(*x, *y))
+            .collect();
+        pick(&options, view, rng)
+    }
+}
+
+/// Fires at the middle half of the board on each axis.
+pub struct CenterStrategy;
+
+impl TargetingStrategy for CenterStrategy {
+    fn next_target(&mut self, view: &TargetView, rng: &mut dyn RngCore) -> (u32, u32) {
+        let width = view.get_width();
+        let height = view.get_height();
+        let options: Vec<(u32, u32)> = view
+            .unknown_cells()
+            .into_iter()
+            .filter(|(x, y)| {
+                (width / 4..=width * 3 / 4).contains(x) && (height / 4..=height * 3 / 4).contains(y)
+            })
+            .collect();
+        pick(&options, view, rng)
+    }
+}
+
+/// Picks one of several strategies for each shot, in proportion to their weights.
+pub struct MixedStrategy {
+    parts: Vec<(u32, Box<dyn TargetingStrategy>)>,
+}
+
+impl MixedStrategy {
+    pub fn new(parts: Vec<(u32, Box<dyn TargetingStrategy>)>) -> Self {
+        Self { parts }
+    }
+}
+
+impl TargetingStrategy for MixedStrategy {
+    fn next_target(&mut self, view: &TargetView, rng: &mut dyn RngCore) -> (u32, u32) {
+        let total: u32 = self.parts.iter().map(|(weight, _)| weight).sum();
+        if total == 0 {
+            return RandomStrategy.next_target(view, rng);
+        }
+
+        let mut roll = rng.gen_range(0..total);
+        for (weight, strategy) in self.parts.iter_mut() {
+            if roll < *weight {
+                return strategy.next_target(view, rng);
+            }
+            roll -= *weight;
+        }
+        unreachable!()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board::Board, GameState::GameRng, Rules::Rules, Ship::Orientation};
+
+    fn classic_board() -> Board {
+        Board::new(&Rules::new())
+    }
+
+    /// A board with the carrier along row 3 from C3 to G3.
+    fn carrier_board() -> Board {
+        let mut board = classic_board();
+        let carrier = board.get_fleet().ships()[4].clone();
+        board
+            .place(&carrier, 2, 2, Orientation::Horizontal)
+            .unwrap();
+        board
+    }
+
+    /// Every target the strategy picks on the board over a run of seeds.
+    fn targets(strategy: &mut dyn TargetingStrategy, board: &Board) -> Vec<(u32, u32)> {
+        let view = TargetView::from_board(board);
+        (0..50)
+            .map(|seed| strategy.next_target(&view, &mut GameRng::seed_from_u64(seed)))
+            .collect()
+    }
+
+    #[test]
+    fn hunt_target_hunts_until_it_hits() {
+        let mut board = carrier_board();
+        board.fire_at(0, 0);
+        let mut strategy = HuntTargetStrategy::new(Box::new(RandomStrategy));
+        assert_eq!(
+            targets(&mut strategy, &board),
+            targets(&mut RandomStrategy, &board)
+        );
+    }
+
+    #[test]
+    fn hunt_target_probes_around_a_lone_hit() {
+        let mut board = carrier_board();
+        board.fire_at(4, 2);
+        board.fire_at(4, 1);
+        let mut strategy = HuntTargetStrategy::new(Box::new(RandomStrategy));
+        for target in targets(&mut strategy, &board) {
+            assert!([(3, 2), (5, 2), (4, 3)].contains(&target));
+        }
+    }
+
+    #[test]
+    fn hunt_target_follows_a_line_both_ways() {
+        let mut board = carrier_board();
+        board.fire_at(4, 2);
+        board.fire_at(5, 2);
+        let mut strategy = HuntTargetStrategy::new(Box::new(RandomStrategy));
+        let picked = targets(&mut strategy, &board);
+        assert!(picked
+            .iter()
+            .all(|target| [(3, 2), (6, 2)].contains(target)));
+        assert!(picked.contains(&(3, 2)) && picked.contains(&(6, 2)));
+
+        // Once one end is a miss, only the other is left
+        board.fire_at(6, 2);
+        board.fire_at(7, 2);
+        for target in targets(&mut strategy, &board) {
+            assert_eq!(target, (3, 2));
+        }
+    }
+
+    #[test]
+    fn density_peaks_in_the_middle_of_an_empty_board() {
+        let view = TargetView::from_board(&classic_board());
+        let density = ProbabilityStrategy::density(&view);
+        let at = |x: u32, y: u32| density[(y * 10 + x) as usize];
+        assert!(at(4, 4) > at(0, 4));
+        assert!(at(0, 4) > at(0, 0));
+        assert_eq!(at(0, 0), at(9, 9));
+        assert_eq!(at(2, 7), at(7, 2));
+    }
+
+    #[test]
+    fn density_leaves_out_misses_and_leans_towards_hits() {
+        let mut board = carrier_board();
+        board.fire_at(0, 0);
+        board.fire_at(4, 2);
+        let view = TargetView::from_board(&board);
+        let density = ProbabilityStrategy::density(&view);
+        assert_eq!(density[0], 0.0);
+
+        let best = ProbabilityStrategy.next_target(&view, &mut GameRng::seed_from_u64(0));
+        assert!([(3, 2), (5, 2), (4, 1), (4, 3)].contains(&best));
+    }
+
+    #[test]
+    fn parity_spaces_by_the_smallest_ship() {
+        // The destroyer is 2 long, so every other cell
+        let candidates = ParityStrategy::candidates(&TargetView::from_board(&classic_board()));
+        assert_eq!(candidates.len(), 50);
+        assert!(candidates.iter().all(|(x, y)| (x + y) % 2 == 0));
+
+        // With it sunk the smallest ship is 3 long
+        let mut board = classic_board();
+        let destroyer = board.get_fleet().ships()[0].clone();
+        board
+            .place(&destroyer, 0, 0, Orientation::Horizontal)
+            .unwrap();
+        board.fire_at(0, 0);
+        board.fire_at(1, 0);
+        let candidates = ParityStrategy::candidates(&TargetView::from_board(&board));
+        let offset = (candidates[0].0 + candidates[0].1) % 3;
+        assert!(candidates.iter().all(|(x, y)| (x + y) % 3 == offset));
+        assert!(!candidates.contains(&(0, 0)));
+    }
+
+    #[test]
+    fn parity_keeps_to_the_pattern_already_fired_on() {
+        let mut board = classic_board();
+        board.fire_at(1, 0);
+        board.fire_at(0, 1);
+
+        let candidates = ParityStrategy::candidates(&TargetView::from_board(&board));
+        assert_eq!(candidates.len(), 48);
+        assert!(candidates.iter().all(|(x, y)| (x + y) % 2 == 1));
+    }
+
+    #[test]
+    fn parity_has_nothing_left_on_a_full_board() {
+        let mut board = classic_board();
+        for y in 0..board.get_height() {
+            for x in 0..board.get_width() {
+                board.fire_at(x, y);
+            }
+        }
+        assert!(ParityStrategy::candidates(&TargetView::from_board(&board)).is_empty());
+    }
+}
@@ -0,0 +1,575 @@
+use crate::{
+    Board::{Board, FireResult, Shot},
+    Difficulty::Difficulty,
+    Engine::{EnginePlacement, EngineStrategy},
+    Fleet::FleetConfig,
+    Placement::{
+        AntiDensityPlacement, ClusteredPlacement, EdgePlacement, PlacementStrategy,
+        SpreadPlacement, UniformPlacement,
+    },
+    Rules::{Adjacency, Neighbourhood},
+    Ship::ShipType,
+    Strategies::{
+        CenterStrategy, CornerStrategy, HuntTargetStrategy, MixedStrategy, ParityStrategy,
+        ProbabilityStrategy, RandomStrategy, SmartStrategy,
+    },
+};
+use rand::RngCore;
+
+/// What the shooter knows about a single cell of the opponent's board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Knowledge {
+    Unknown,
+    Miss,
+    Hit,
+    Sunk,
+    /// Never fired at, but the placement rules mean it can not hold a ship.
+    Water,
+    /// Already chosen for the salvo being picked.
+    Targeted,
+}
+
+/// The opponent's board as seen by the shooter: only the shots fired at it
+/// and what each one reported.
+#[derive(Clone, Debug)]
+pub struct TargetView {
+    width: u32,
+    height: u32,
+    cells: Vec<Knowledge>,
+    fleet: FleetConfig,
+    adjacency: Adjacency,
+    shots: Vec<Shot>,
+}
+
+impl TargetView {
+    pub fn from_board(board: &Board) -> Self {
+        Self::from_shots(
+            board.get_width(),
+            board.get_height(),
+            board.get_fleet(),
+            board.get_adjacency(),
+            board.get_shots(),
+        )
+    }
+
+    /// The view of a board of the given size and rules after `shots`, in the
+    /// order they were fired.
+    pub fn from_shots(
+        width: u32,
+        height: u32,
+        fleet: &FleetConfig,
+        adjacency: Adjacency,
+        shots: &[Shot],
+    ) -> Self {
+        let mut cells = vec![Knowledge::Unknown; (width * height) as usize];
+        for shot in shots {
+            let knowledge = match shot.result {
+                FireResult::Miss => Knowledge::Miss,
+                _ => Knowledge::Hit,
+            };
+            cells[(shot.y * width + shot.x) as usize] = knowledge;
+        }
+
+        let mut view = Self {
+            width,
+            height,
+            cells,
+            fleet: fleet.clone(),
+            adjacency,
+            shots: shots.to_vec(),
+        };
+        view.mark_sunk();
+        view.mark_water();
+        view
+    }
+
+    /// Works out which hits belong to sunk ships. A sunk ship lies in a
+    /// straight line through the shot that sank it, over hits fired no later
+    /// than that shot. Where only one such line is left it is the ship, and
+    /// its cells are taken out of the lines of other sunk ships. Otherwise
+    /// only the cells every line covers are known to be sunk.
+    fn mark_sunk(&mut self) {
+        let mut hit_order = vec![None; self.cells.len()];
+        let mut sinks = Vec::new();
+        for (index, shot) in self.shots.iter().enumerate() {
+            hit_order[(shot.y * self.width + shot.x) as usize] = Some(index);
+            if let FireResult::Sunk(ship_type) = &shot.result {
+                sinks.push((index, shot.x, shot.y, ship_type.get_size()));
+            }
+        }
+
+        let mut claimed = vec![false; self.cells.len()];
+        let mut lines: Vec<Vec<Vec<usize>>> = vec![Vec::new(); sinks.len()];
+        let mut resolved = vec![false; sinks.len()];
+        let mut progress = true;
+        while progress {
+            progress = false;
+            for (sink, &(index, x, y, size)) in sinks.iter().enumerate() {
+                if resolved[sink] {
+                    continue;
+                }
+                lines[sink] = self
+                    .lines_through(x, y, size)
+                    .into_iter()
+                    .filter(|line| {
+                        line.iter().all(|&cell| {
+                            !claimed[cell] && hit_order[cell].is_some_and(|order| order <= index)
+                        })
+                    })
+                    .collect();
+                if lines[sink].len() == 1 {
+                    for &cell in &lines[sink][0] {
+                        claimed[cell] = true;
+                    }
+                    resolved[sink] = true;
+                    progress = true;
+                }
+            }
+        }
+
+        for (sink, &(_, x, y, _)) in sinks.iter().enumerate() {
+            self.cells[(y * self.width + x) as usize] = Knowledge::Sunk;
+            let Some((first, rest)) = lines[sink].split_first() else {
+                continue;
+            };
+            for &cell in first {
+                if rest.iter().all(|line| line.contains(&cell)) {
+                    self.cells[cell] = Knowledge::Sunk;
+                }
+            }
+        }
+    }
+
+    /// The cells, as indices, of every straight line of `size` cells through
+    /// (x, y) that fits on the board.
+    fn lines_through(&self, x: u32, y: u32, size: u32) -> Vec<Vec<usize>> {
+        let mut lines = Vec::new();
+        for horizontal in [true, false] {
+            let (along, length) = match horizontal {
+                true => (x, self.width),
+                false => (y, self.height),
+            };
+            let first = (along + 1).saturating_sub(size);
+            for start in first..=along {
+                if start + size > length {
+                    break;
+                }
+                let line = (start..start + size)
+                    .map(|position| match horizontal {
+                        true => (y * self.width + position) as usize,
+                        false => (position * self.width + x) as usize,
+                    })
+                    .collect();
+                lines.push(line);
+            }
+            // A ship of one cell is the same line either way
+            if size == 1 {
+                break;
+            }
+        }
+        lines
+    }
+
+    /// Marks the cells that the adjacency rule keeps clear around what is already known.
+    fn mark_water(&mut self) {
+        let mut water = Vec::new();
+        for (x, y) in self.cells_with(Knowledge::Sunk) {
+            water.extend(self.adjacency.neighbours(x, y, self.width, self.height));
+        }
+        // Ships are straight, so a hit's diagonals can only be another ship,
+        // which this rule does not allow
+        if self.adjacency == Adjacency::NoTouching {
+            for (x, y) in self.hits() {
+                water.extend(
+                    self.neighbours(x, y, Neighbourhood::All)
+                        .into_iter()
+                        .filter(|(water_x, water_y)| *water_x != x && *water_y != y),
+                );
+            }
+        }
+
+        for (x, y) in water {
+            if self.is_unknown(x, y) {
+                self.cells[(y * self.width + x) as usize] = Knowledge::Water;
+            }
+        }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_fleet(&self) -> &FleetConfig {
+        &self.fleet
+    }
+
+    pub fn get_adjacency(&self) -> Adjacency {
+        self.adjacency
+    }
+
+    /// The shots fired so far, in order.
+    pub fn get_shots(&self) -> &Vec<Shot> {
+        &self.shots
+    }
+
+    /// Marks an unknown cell as chosen for the salvo being picked.
+    pub fn mark_targeted(&mut self, x: u32, y: u32) {
+        if self.is_unknown(x, y) {
+            self.cells[(y * self.width + x) as usize] = Knowledge::Targeted;
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<Knowledge> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.cells[(y * self.width + x) as usize])
+    }
+
+    pub fn is_unknown(&self, x: u32, y: u32) -> bool {
+        self.get(x, y) == Some(Knowledge::Unknown)
+    }
+
+    pub fn unknown_cells(&self) -> Vec<(u32, u32)> {
+        self.cells_with(Knowledge::Unknown)
+    }
+
+    /// Hit cells not known to belong to a sunk ship.
+    pub fn hits(&self) -> Vec<(u32, u32)> {
+        self.cells_with(Knowledge::Hit)
+    }
+
+    /// The ships of the fleet that have not been sunk yet.
+    pub fn remaining_ships(&self) -> Vec<ShipType> {
+        let mut remaining = self.fleet.ships();
+        for shot in &self.shots {
+            if let FireResult::Sunk(sunk) = &shot.result {
+                if let Some(index) = remaining.iter().position(|ship_type| ship_type == sunk) {
+                    remaining.remove(index);
+                }
+            }
+        }
+        remaining
+    }
+
+    /// On board cells around the given one.
+    pub fn neighbours(&self, x: u32, y: u32, neighbourhood: Neighbourhood) -> Vec<(u32, u32)> {
+        neighbourhood.cells(x, y, self.width, self.height)
+    }
+
+    fn cells_with(&self, knowledge: Knowledge) -> Vec<(u32, u32)> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|(x, y)| self.get(*x, *y) == Some(knowledge))
+            .collect()
+    }
+}
+
+/// Picks where the AI fires next from what it knows about the opponent's board.
+pub trait TargetingStrategy {
+    /// Returns the cell to fire at. Cells that are off the board or not
+    /// `Knowledge::Unknown` are rejected and the strategy is asked again.
+    fn next_target(&mut self, view: &TargetView, rng: &mut dyn RngCore) -> (u32, u32);
+
+    /// Returns `count` cells to fire at together, before any of their results
+    /// are known. By default each cell is picked with `next_target` as if the
+    /// ones picked before it were already fired at.
+    fn next_salvo(
+        &mut self,
+        view: &TargetView,
+        count: u32,
+        rng: &mut dyn RngCore,
+    ) -> Vec<(u32, u32)> {
+        let mut view = view.clone();
+        let mut targets = Vec::new();
+        for _ in 0..count {
+            let (x, y) = self.next_target(&view, rng);
+            view.mark_targeted(x, y);
+            targets.push((x, y));
+        }
+        targets
+    }
+
+    /// Why the strategy can not pick targets any more, such as an engine
+    /// that stopped. The AI gives this as its error instead of firing.
+    fn error(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+pub type StrategyFactory = Box<dyn Fn() -> Box<dyn TargetingStrategy>>;
+pub type PlacementFactory = Box<dyn Fn() -> Box<dyn PlacementStrategy>>;
+
+/// Named targeting and placement strategies, and which of them each difficulty uses.
+pub struct StrategyRegistry {
+    factories: Vec<(String, StrategyFactory)>,
+    difficulties: Vec<(Difficulty, String)>,
+    placement_factories: Vec<(String, PlacementFactory)>,
+    difficulty_placements: Vec<(Difficulty, String)>,
+    /// Strategies that run outside the game, so games using them cannot be saved.
+    unsaveable: Vec<String>,
+}
+
+impl StrategyRegistry {
+    /// A registry holding the built in strategies.
+    pub fn new() -> Self {
+        let mut registry = Self {
+            factories: Vec::new(),
+            difficulties: Vec::new(),
+            placement_factories: Vec::new(),
+            difficulty_placements: Vec::new(),
+            unsaveable: Vec::new(),
+        };
+
+        registry.register("random", || Box::new(RandomStrategy));
+        registry.register("smart", || Box::new(SmartStrategy));
+        registry.register("corner", || Box::new(CornerStrategy));
+        registry.register("center", || Box::new(CenterStrategy));
+        registry.register("random-smart", || {
+            Box::new(MixedStrategy::new(vec![
+                (1, Box::new(RandomStrategy)),
+                (1, Box::new(SmartStrategy)),
+            ]))
+        });
+        registry.register("corner-center-smart", || {
+            Box::new(MixedStrategy::new(vec![
+                (1, Box::new(CornerStrategy)),
+                (1, Box::new(CenterStrategy)),
+                (2, Box::new(SmartStrategy)),
+            ]))
+        });
+
+        registry.register("hunt-target", || {
+            Box::new(HuntTargetStrategy::new(Box::new(RandomStrategy)))
+        });
+        registry.register("parity", || Box::new(ParityStrategy));
+        registry.register("hunt-target-parity", || {
+            Box::new(HuntTargetStrategy::new(Box::new(ParityStrategy)))
+        });
+        registry.register("probability", || Box::new(ProbabilityStrategy));
+
+        registry.assign(Difficulty::Easy, "random").unwrap();
+        registry.assign(Difficulty::Medium, "hunt-target").unwrap();
+        registry
+            .assign(Difficulty::Hard, "hunt-target-parity")
+            .unwrap();
+        registry.assign(Difficulty::Expert, "probability").unwrap();
+
+        registry.register_placement("uniform", || Box::new(UniformPlacement));
+        registry.register_placement("edge-hugging", || Box::new(EdgePlacement));
+        registry.register_placement("spread-out", || Box::new(SpreadPlacement));
+        registry.register_placement("clustered", || Box::new(ClusteredPlacement));
+        registry.register_placement("anti-density", || Box::new(AntiDensityPlacement::new()));
+
+        for difficulty in [Difficulty::Easy, Difficulty::Medium] {
+            registry.assign_placement(difficulty, "uniform").unwrap();
+        }
+        registry
+            .assign_placement(Difficulty::Hard, "spread-out")
+            .unwrap();
+        registry
+            .assign_placement(Difficulty::Expert, "anti-density")
+            .unwrap();
+        registry
+    }
+
+    /// Adds a strategy, replacing any registered under the same name.
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn TargetingStrategy> + 'static,
+    {
+        self.factories.retain(|(existing, _)| existing != name);
+        self.factories.push((name.to_string(), Box::new(factory)));
+        self.unsaveable.retain(|existing| existing != name);
+    }
+
+    /// Adds an external engine, started with `command`, as both a strategy
+    /// and a placement strategy called `name`.
+    pub fn register_engine(&mut self, name: &str, command: &str) {
+        let targeting = command.to_string();
+        self.register(name, move || Box::new(EngineStrategy::new(&targeting)));
+        let placement = command.to_string();
+        self.register_placement(name, move || Box::new(EnginePlacement::new(&placement)));
+        self.unsaveable.push(name.to_string());
+    }
+
+    /// Whether a game whose AI uses the strategy or placement strategy called
+    /// `name` can be saved.
+    pub fn can_save(&self, name: &str) -> bool {
+        !self.unsaveable.iter().any(|existing| existing == name)
+    }
+
+    /// Makes a difficulty use a registered strategy.
+    pub fn assign(&mut self, difficulty: Difficulty, name: &str) -> Result<(), &'static str> {
+        if !self.contains(name) {
+            return Err("No strategy is registered under this name");
+        }
+        self.difficulties
+            .retain(|(existing, _)| *existing != difficulty);
+        self.difficulties.push((difficulty, name.to_string()));
+        Ok(())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.iter().any(|(existing, _)| existing == name)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.factories
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// The name of the strategy a difficulty uses, falling back to "random".
+    pub fn strategy_for(&self, difficulty: Difficulty) -> &str {
+        self.difficulties
+            .iter()
+            .find(|(existing, _)| *existing == difficulty)
+            .map_or("random", |(_, name)| name.as_str())
+    }
+
+    pub fn create(&self, name: &str) -> Option<Box<dyn TargetingStrategy>> {
+        self.factories
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, factory)| factory())
+    }
+
+    /// Adds a placement strategy, replacing any registered under the same name.
+    pub fn register_placement<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn PlacementStrategy> + 'static,
+    {
+        self.placement_factories
+            .retain(|(existing, _)| existing != name);
+        self.placement_factories
+            .push((name.to_string(), Box::new(factory)));
+        self.unsaveable.retain(|existing| existing != name);
+    }
+
+    /// Makes a difficulty place its fleet with a registered placement strategy.
+    pub fn assign_placement(
+        &mut self,
+        difficulty: Difficulty,
+        name: &str,
+    ) -> Result<(), &'static str> {
+        if !self.contains_placement(name) {
+            return Err("No placement strategy is registered under this name");
+        }
+        self.difficulty_placements
+            .retain(|(existing, _)| *existing != difficulty);
+        self.difficulty_placements
+            .push((difficulty, name.to_string()));
+        Ok(())
+    }
+
+    pub fn contains_placement(&self, name: &str) -> bool {
+        self.placement_factories
+            .iter()
+            .any(|(existing, _)| existing == name)
+    }
+
+    pub fn placement_names(&self) -> Vec<&str> {
+        self.placement_factories
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// The name of the placement strategy a difficulty uses, falling back to "uniform".
+    pub fn placement_for(&self, difficulty: Difficulty) -> &str {
+        self.difficulty_placements
+            .iter()
+            .find(|(existing, _)| *existing == difficulty)
+            .map_or("uniform", |(_, name)| name.as_str())
+    }
+
+    pub fn create_placement(&self, name: &str) -> Option<Box<dyn PlacementStrategy>> {
+        self.placement_factories
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, factory)| factory())
+    }
+}
+
+impl Default for StrategyRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Rules::Rules, Ship::Orientation};
+
+    /// A destroyer along the top row with a cruiser touching it from below.
+    fn touching_ships() -> Board {
+        let mut board = Board::new(&Rules::new());
+        let ships = board.get_fleet().ships();
+        board
+            .place(&ships[0], 0, 0, Orientation::Horizontal)
+            .unwrap();
+        board
+            .place(&ships[2], 0, 1, Orientation::Horizontal)
+            .unwrap();
+        board
+    }
+
+    #[test]
+    fn a_sunk_ship_only_shows_what_its_shots_prove() {
+        let mut board = touching_ships();
+        for (x, y) in [(0, 0), (0, 1), (1, 1), (1, 0)] {
+            board.fire_at(x, y);
+        }
+
+        // The destroyer could lie along the row or down the column
+        let view = TargetView::from_board(&board);
+        assert_eq!(view.get(1, 0), Some(Knowledge::Sunk));
+        assert_eq!(view.hits(), vec![(0, 0), (0, 1), (1, 1)]);
+        assert_eq!(view.remaining_ships().len(), 4);
+    }
+
+    #[test]
+    fn sinking_another_ship_can_settle_an_earlier_one() {
+        let mut board = touching_ships();
+        for (x, y) in [(0, 0), (0, 1), (1, 1), (1, 0), (2, 1)] {
+            board.fire_at(x, y);
+        }
+
+        // The cruiser can only be the row below, which leaves the destroyer the top row
+        let view = TargetView::from_board(&board);
+        assert!(view.hits().is_empty());
+        assert_eq!(view.get(0, 0), Some(Knowledge::Sunk));
+        assert_eq!(view.remaining_ships().len(), 3);
+    }
+
+    #[test]
+    fn no_touching_rules_out_the_cells_around_a_sunk_ship() {
+        let mut rules = Rules::new();
+        rules.adjacency = Adjacency::NoTouching;
+        let mut board = Board::new(&rules);
+        let destroyer = board.get_fleet().ships()[0].clone();
+        board
+            .place(&destroyer, 4, 4, Orientation::Vertical)
+            .unwrap();
+        board.fire_at(4, 4);
+
+        // Before it sinks only the hit's diagonals are known to be clear
+        let view = TargetView::from_board(&board);
+        assert_eq!(view.get(3, 3), Some(Knowledge::Water));
+        assert_eq!(view.get(4, 3), Some(Knowledge::Unknown));
+
+        board.fire_at(4, 5);
+        let view = TargetView::from_board(&board);
+        assert_eq!(view.get(4, 5), Some(Knowledge::Sunk));
+        assert_eq!(view.get(4, 3), Some(Knowledge::Water));
+        assert_eq!(view.get(4, 6), Some(Knowledge::Water));
+        assert_eq!(view.unknown_cells().len(), 100 - 12);
+    }
+}
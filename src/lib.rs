@@ -0,0 +1,18 @@
+#![allow(non_snake_case)]
+
+pub mod AI;
+pub mod Board;
+pub mod Cell;
+pub mod Difficulty;
+pub mod Engine;
+pub mod Fleet;
+pub mod GameState;
+pub mod MoveLog;
+pub mod Network;
+pub mod Placement;
+pub mod Rules;
+pub mod Save;
+pub mod Ship;
+pub mod Simulation;
+pub mod Strategies;
+pub mod Targeting;
@@ -1,11 +1,79 @@
-pub mod Board;
-pub mod Game;
-pub mod Ship;
-pub mod Difficulty;
-pub mod AI;
+#![allow(non_snake_case)]
+
+use std::path::PathBuf;
+
+mod Game;
+mod Online;
+mod Replay;
+mod Simulate;
 
 fn main() {
-    let mut game = Game::Game::new();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [] => {
+            let mut game = Game::Game::new();
 
-    game.start_game();
+            game.start_game();
+        }
+        [command, options @ ..] if command == "simulate" => {
+            let simulate = match Simulate::Simulate::from_args(options) {
+                Ok(simulate) => simulate,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    eprintln!("Usage: {}", Simulate::USAGE);
+                    std::process::exit(2);
+                }
+            };
+            // An AI can still fail part way, such as an engine that stops
+            if let Err(e) = simulate.run() {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        [command, port] if command == "host" => match port.parse() {
+            Ok(port) => {
+                if let Err(e) = Online::Online::host(port) {
+                    eprintln!("{}", e);
+                }
+            }
+            Err(_) => {
+                eprintln!("The port must be a number from 0 to 65535");
+                std::process::exit(2);
+            }
+        },
+        [command, address] if command == "join" => {
+            if let Err(e) = Online::Online::join(address) {
+                eprintln!("{}", e);
+            }
+        }
+        [flag, seed] if flag == "--seed" => match seed.parse() {
+            Ok(seed) => Game::Game::with_seed(seed).start_game(),
+            Err(_) => {
+                eprintln!("The seed must be a whole number");
+                std::process::exit(2);
+            }
+        },
+        [flag, command] if flag == "--engine" => match Game::Game::with_engine(command) {
+            Ok(mut game) => game.start_game(),
+            Err(e) => eprintln!("{}", e),
+        },
+        [flag, path] if flag == "--load" => match Game::Game::load(PathBuf::from(path)) {
+            Ok(mut game) => game.continue_game(),
+            Err(e) => eprintln!("{}", e),
+        },
+        [flag, path] if flag == "--replay" => match Replay::Replay::load(&PathBuf::from(path)) {
+            Ok(mut replay) => replay.run(),
+            Err(e) => eprintln!("{}", e),
+        },
+        _ => {
+            eprintln!(
+                "Usage: battleships [--seed <n> | --load <file> | --replay <file> | --engine <command>]"
+            );
+            eprintln!("       battleships host <port>");
+            eprintln!("       battleships join <address>:<port>");
+            eprintln!("       {}", Simulate::USAGE);
+            std::process::exit(2);
+        }
+    }
 }